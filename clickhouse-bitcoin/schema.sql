@@ -13,7 +13,7 @@ CREATE TABLE IF NOT EXISTS bitcoin_utxos (
     spent_in_tx_id String DEFAULT '', -- Transaction ID that spent this UTXO
     spent_at_block_height UInt64 DEFAULT 0, -- Block height when this UTXO was spent
     spent_at_block_time DateTime64(3) DEFAULT '1970-01-01 00:00:00.000' -- Block timestamp when this UTXO was spent
-) ENGINE = ReplacingMergeTree(block_height)
+) ENGINE = ReplacingMergeTree(spent_at_block_height) -- The spend row, with a non-zero spent_at_block_height, replaces the creation row
 ORDER BY (address, tx_id, vout_index);
 
 -- Create a table to store token balance snapshots
@@ -1,19 +1,65 @@
 -- ClickHouse schema for Bitcoin UTXO tracking and token balances
 
+-- Create a table to store block metrics, filled by the map_block_metrics module
+CREATE TABLE IF NOT EXISTS blocks (
+    number UInt64,               -- Block height
+    hash String,                 -- Block hash
+    timestamp DateTime64(3),     -- Block timestamp
+    size UInt32,                 -- Serialized size in bytes
+    weight UInt32,               -- Weight in weight units
+    tx_count UInt32,             -- Number of transactions
+    difficulty Float64,          -- Block difficulty
+    miner String,                -- Mining pool name
+    miner_match_method String,   -- PAYOUT_ADDRESS, COINBASE_TAG or UNKNOWN
+    block_time UInt32,           -- Seconds since the previous block, clamped at 0
+    block_interval Int64,        -- Signed seconds since the previous block
+    median_time DateTime64(3),   -- Median time past (BIP113)
+    total_fees UInt64,           -- Total fees in satoshis, 0 unless fees_resolved
+    fees_resolved UInt8,         -- Whether every spent output was resolved
+    coinbase_fees UInt64,        -- Coinbase output value minus the subsidy in satoshis
+    block_reward UInt64,         -- Block subsidy in satoshis
+    version UInt32,              -- Block version
+    nonce UInt32,                -- Header nonce
+    bits UInt32,                 -- Compact difficulty target
+    target String,               -- Target decoded from bits, 64 hex digits
+    work String,                 -- Block work, 64 hex digits
+    chainwork String,            -- Cumulative chain work, 64 hex digits
+    version_bits UInt32,         -- Bits signaled by the version (BIP9), 0 without version bits
+    segwit_percent Float64,      -- Share of transactions with witness data, in percent
+    taproot_percent Float64,     -- Share of transactions spending or creating taproot outputs, in percent
+    segwit_tx_count UInt32,      -- Transactions with witness data
+    nested_segwit_tx_count UInt32, -- Transactions with a P2SH-wrapped witness input
+    taproot_tx_count UInt32,     -- Transactions spending or creating taproot outputs
+    input_count UInt32,          -- Non-coinbase inputs
+    segwit_input_count UInt32,   -- Native, nested and taproot witness inputs
+    nested_segwit_input_count UInt32, -- P2SH-wrapped P2WPKH and P2WSH inputs
+    taproot_input_count UInt32,  -- Key-path and script-path taproot inputs
+    output_count UInt32,         -- Outputs
+    segwit_output_count UInt32,  -- Witness program outputs, P2TR included
+    taproot_output_count UInt32, -- P2TR outputs
+    witness_bytes UInt64,        -- Serialized witness bytes, marker and flag included
+    witness_weight_saved UInt64  -- Weight saved by the witness discount
+) ENGINE = ReplacingMergeTree()
+ORDER BY (number);
+
 -- Create a table to store raw UTXO data
 CREATE TABLE IF NOT EXISTS bitcoin_utxos (
     tx_id String,                -- Transaction ID
     vout_index UInt32,           -- Output index in the transaction
     value UInt64,                -- Value in satoshis
     script_type String,          -- Type of script (P2PKH, P2SH, P2WPKH, etc.)
+    multisig_required UInt32 DEFAULT 0, -- m of an m-of-n bare multisig script
+    multisig_total UInt32 DEFAULT 0, -- n of an m-of-n bare multisig script
     address String,              -- Bitcoin address (if can be derived)
     block_height UInt64,         -- Block height when this UTXO was created
     block_time DateTime64(3),    -- Block timestamp when this UTXO was created
     is_spent UInt8 DEFAULT 0,    -- Flag to indicate if the UTXO has been spent
     spent_in_tx_id String DEFAULT '', -- Transaction ID that spent this UTXO
+    spent_in_input_index UInt32 DEFAULT 0, -- Input index in the spending transaction
     spent_at_block_height UInt64 DEFAULT 0, -- Block height when this UTXO was spent
-    spent_at_block_time DateTime64(3) DEFAULT '1970-01-01 00:00:00.000' -- Block timestamp when this UTXO was spent
-) ENGINE = ReplacingMergeTree(block_height)
+    spent_at_block_time DateTime64(3) DEFAULT '1970-01-01 00:00:00.000', -- Block timestamp when this UTXO was spent
+    spend_type String DEFAULT '' -- How the spending input spent it (P2PKH, P2SH_P2WPKH, P2TR_KEY_PATH, etc.)
+) ENGINE = ReplacingMergeTree(spent_at_block_height) -- The spend row, with a non-zero spent_at_block_height, replaces the creation row
 ORDER BY (address, tx_id, vout_index);
 
 -- Create a table to store token balance snapshots
@@ -55,75 +101,38 @@ ORDER BY balance DESC;
 -- Create a table to store transaction data for reference
 CREATE TABLE IF NOT EXISTS bitcoin_transactions (
     tx_id String,                -- Transaction ID
+    wtxid String,                -- Witness transaction ID, equal to tx_id without witness data
     block_height UInt64,         -- Block height
     block_time DateTime64(3),    -- Block timestamp
     fee UInt64,                  -- Transaction fee in satoshis
+    fee_rate Float64,            -- Fee rate in sat/vB
+    size UInt32,                 -- Serialized size in bytes
+    weight UInt32,               -- Weight in weight units
+    is_coinbase UInt8,           -- 1 for the coinbase transaction
+    has_witness UInt8,           -- 1 if any input carries witness data
+    tx_type String,              -- COINBASE, TAPROOT, SEGWIT or LEGACY
     input_count UInt32,          -- Number of inputs
     output_count UInt32,         -- Number of outputs
-    total_input_value UInt64,    -- Total value of inputs in satoshis
-    total_output_value UInt64    -- Total value of outputs in satoshis
+    total_input_value UInt64,    -- Total value of inputs in satoshis, 0 unless inputs_resolved
+    total_output_value UInt64,   -- Total value of outputs in satoshis
+    inputs_resolved UInt8        -- 1 if every spent output was resolved, so the fee is known
 ) ENGINE = ReplacingMergeTree(block_height)
 ORDER BY (tx_id, block_height);
 
--- Create a table to store address transaction history
+-- Create a table to store address transaction history, filled by the map_address_activity module
 CREATE TABLE IF NOT EXISTS bitcoin_address_transactions (
     address String,              -- Bitcoin address
     tx_id String,                -- Transaction ID
     block_height UInt64,         -- Block height
     block_time DateTime64(3),    -- Block timestamp
     is_input UInt8,              -- 1 if address is an input, 0 if output
+    io_index UInt32,             -- Index of the input or output in the transaction
     value UInt64,                -- Value in satoshis
-    balance_after UInt64         -- Balance after this transaction
+    script_type String,          -- Type of script (P2PKH, P2SH, P2WPKH, etc.)
+    address_tag String,          -- Known entity owning the address (mining pool name)
+    address_category String      -- Category of the known entity (MINING_POOL)
 ) ENGINE = MergeTree()
-ORDER BY (address, block_height, tx_id, is_input);
-
--- Create a materialized view to update address transaction history
-CREATE MATERIALIZED VIEW IF NOT EXISTS bitcoin_address_transactions_mv
-TO bitcoin_address_transactions
-AS
-WITH
-    -- Get all outputs for addresses
-    outputs AS (
-        SELECT
-            address,
-            tx_id,
-            block_height,
-            block_time,
-            0 AS is_input,
-            value
-        FROM bitcoin_utxos
-    ),
-    -- Get all inputs for addresses
-    inputs AS (
-        SELECT
-            address,
-            spent_in_tx_id AS tx_id,
-            spent_at_block_height AS block_height,
-            spent_at_block_time AS block_time,
-            1 AS is_input,
-            value
-        FROM bitcoin_utxos
-        WHERE is_spent = 1
-    ),
-    -- Combine inputs and outputs
-    all_txs AS (
-        SELECT * FROM outputs
-        UNION ALL
-        SELECT * FROM inputs
-    )
-SELECT
-    address,
-    tx_id,
-    block_height,
-    block_time,
-    is_input,
-    value,
-    sum(if(is_input = 1, -value, value)) OVER (
-        PARTITION BY address
-        ORDER BY block_height, tx_id, is_input
-        ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW
-    ) AS balance_after
-FROM all_txs;
+ORDER BY (address, block_height, tx_id, is_input, io_index);
 
 -- Create a table for rich list (top addresses by balance)
 CREATE MATERIALIZED VIEW IF NOT EXISTS bitcoin_rich_list
@@ -140,29 +149,77 @@ FROM bitcoin_token_balances_latest
 ORDER BY balance DESC
 LIMIT 1000;
 
--- Create a table for daily statistics
+-- Create a table for daily statistics, filled by the map_daily_metrics module once each UTC day is over
 CREATE TABLE IF NOT EXISTS bitcoin_daily_stats (
-    date Date,                   -- Date
-    active_addresses UInt64,     -- Number of active addresses
-    transaction_count UInt64,    -- Number of transactions
-    total_volume UInt64,         -- Total volume in satoshis
-    avg_transaction_value UInt64, -- Average transaction value in satoshis
-    new_addresses UInt64,        -- Number of new addresses
-    total_addresses UInt64       -- Total number of addresses
-) ENGINE = SummingMergeTree()
+    date Date,                   -- UTC day
+    avg_block_time Float64,      -- Average interval between blocks in seconds
+    total_tx_count UInt32,       -- Number of transactions
+    total_tx_volume UInt64,      -- Total output value of non-coinbase transactions in satoshis
+    avg_block_size UInt32,       -- Average block size in bytes
+    avg_tx_per_block Float64,    -- Average number of transactions per block
+    avg_fee_rate Float64,        -- Total fees over total vsize in sat/vB
+    mempool_tx_count UInt32,     -- Not observable from blocks, always 0
+    segwit_tx_percent Float64,   -- Share of transactions carrying witness data
+    taproot_tx_percent Float64,  -- Share of transactions spending or creating Taproot outputs
+    avg_difficulty Float64,      -- Average block difficulty
+    active_addresses UInt32      -- Distinct addresses with an input or an output
+) ENGINE = ReplacingMergeTree()
 ORDER BY (date);
 
--- Create a materialized view to update daily statistics
-CREATE MATERIALIZED VIEW IF NOT EXISTS bitcoin_daily_stats_mv
-TO bitcoin_daily_stats
-AS
-SELECT
-    toDate(block_time) AS date,
-    uniqExact(address) AS active_addresses,
-    uniqExact(tx_id) AS transaction_count,
-    sum(if(is_input = 0, value, 0)) AS total_volume,
-    if(uniqExact(tx_id) > 0, sum(if(is_input = 0, value, 0)) / uniqExact(tx_id), 0) AS avg_transaction_value,
-    uniqExact(if(min(block_height) OVER (PARTITION BY address) = block_height, address, null)) AS new_addresses,
-    uniqExact(address) AS total_addresses
-FROM bitcoin_address_transactions
-GROUP BY date;
+-- Create a table for difficulty retargets, filled by the map_epoch_metrics module at each 2016-block boundary
+CREATE TABLE IF NOT EXISTS bitcoin_difficulty_epochs (
+    epoch UInt64,                -- Epoch number, height / 2016
+    start_height UInt64,         -- First block of the epoch
+    end_height UInt64,           -- Last block of the epoch
+    start_time DateTime64(3),    -- Timestamp of the first block
+    end_time DateTime64(3),      -- Timestamp of the last block
+    actual_timespan Int64,       -- Seconds from the first to the last block (2015 intervals, as in consensus)
+    target_timespan Int64,       -- Two weeks in seconds
+    bits UInt32,                 -- Compact target of the epoch
+    expected_bits UInt32,        -- Compact target recomputed with the consensus formula
+    next_bits UInt32,            -- Compact target of the next epoch's first block
+    bits_match UInt8,            -- Whether next_bits equals expected_bits
+    adjustment_percent Float64,  -- Difficulty change in percent
+    hashrate Float64             -- Implied hashes per second
+) ENGINE = ReplacingMergeTree()
+ORDER BY (epoch);
+
+-- Create a table for soft-fork signaling, filled by the map_deployments module at the end of each period
+CREATE TABLE IF NOT EXISTS bitcoin_deployment_periods (
+    deployment String,           -- Deployment name from the module params
+    bit UInt32,                  -- Version bit
+    period UInt64,               -- Period number, height / period_length
+    start_height UInt64,         -- First block of the period
+    end_height UInt64,           -- Last block of the period
+    signal_count UInt32,         -- Blocks of the period signaling the bit
+    threshold UInt32,            -- Signaling blocks needed to lock in
+    period_length UInt32,        -- Blocks per period
+    state String,                -- DEFINED, STARTED, LOCKED_IN, ACTIVE or FAILED during the period
+    next_state String,           -- State during the next period
+    transition UInt8             -- Whether the state changes at the end of the period
+) ENGINE = ReplacingMergeTree()
+ORDER BY (deployment, period);
+
+-- Create a table for ordinals inscriptions, filled by the map_inscriptions module
+CREATE TABLE IF NOT EXISTS bitcoin_inscriptions (
+    inscription_id String,       -- <txid>i<envelope_index>
+    tx_id String,                -- Reveal transaction ID
+    input_index UInt32,          -- Input whose tapscript holds the envelope
+    envelope_index UInt32,       -- Envelope index across the inputs of the transaction
+    block_height UInt64,         -- Block height of the reveal
+    block_time DateTime64(3),    -- Block timestamp of the reveal
+    content_type String,         -- MIME type, empty when absent
+    content_encoding String,     -- Content encoding such as br, empty when absent
+    has_body UInt8,              -- Whether the envelope has a body
+    body_length UInt64,          -- Body size in bytes
+    parents String,              -- Comma-separated parent inscription ids
+    metadata String,             -- CBOR metadata, hex
+    metaprotocol String,         -- Metaprotocol identifier, e.g. brc-20
+    has_pointer UInt8,           -- Whether a pointer is set
+    pointer UInt64,              -- Sat offset into the outputs the inscription goes to
+    delegate String,             -- Inscription id whose content is served instead
+    duplicate_field UInt8,       -- A non-repeatable field appears twice
+    incomplete_field UInt8,      -- The last tag has no value
+    unrecognized_even_field UInt8 -- Unknown even tag, unbound in ord
+) ENGINE = ReplacingMergeTree()
+ORDER BY (inscription_id);
@@ -1,25 +1,453 @@
+// The handler macros expand params into raw pointer reads
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
 mod pb;
 mod utils;
 mod mappers;
 
+use pb::bitcoin::analytics::v1::{
+    AddressActivityRecords, BlockMetricsRecord, BlockTimestamp, DeploymentOutcome, DeploymentPeriodRecords,
+    EpochBoundary, EpochMetricsRecords, InscriptionRecords, NetworkDailyMetricsRecords, TransactionMetricsRecords,
+};
+use pb::bitcoin::utxo::v1::{UTXORecords, UTXO};
 use substreams::errors::Error;
+use substreams::pb::substreams::store_delta::Operation;
+use substreams::store::{
+    DeltaInt64, Deltas, StoreAdd, StoreAddBigInt, StoreAddFloat64, StoreAddInt64, StoreDelete, StoreGet,
+    StoreGetBigInt, StoreGetFloat64, StoreGetInt64, StoreGetProto, StoreMax, StoreMaxInt64, StoreNew, StoreSet,
+    StoreSetIfNotExists, StoreSetIfNotExistsInt64, StoreSetIfNotExistsProto, StoreSetProto,
+};
 use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
 
-/// Maps blocks to block metrics
+/// Keeps the timestamps of the latest block
+#[substreams::handlers::store]
+pub fn store_block_timestamps(block: Block, store: StoreSetProto<BlockTimestamp>) {
+    store.set(0, mappers::block::LAST_BLOCK_KEY, &mappers::block::extract_block_timestamp(&block));
+}
+
+/// Maps blocks to block metrics, `params` holding mining pool overrides
 #[substreams::handlers::map]
-pub fn map_block_metrics(block: Block) -> Result<pb::bitcoin::analytics::v1::BlockMetricsRecord, Error> {
-    let block_data = mappers::block::extract_block_metrics(&block)?;
+pub fn map_block_metrics(
+    params: String,
+    block: Block,
+    utxo_store: StoreGetProto<UTXO>,
+    timestamp_store: StoreGetProto<BlockTimestamp>,
+    chainwork_store: StoreGetBigInt,
+) -> Result<pb::bitcoin::analytics::v1::BlockMetricsRecord, Error> {
+    // The store state before this block was applied holds the parent block
+    let previous = timestamp_store.get_first(mappers::block::LAST_BLOCK_KEY);
+    let pools = utils::pools::PoolDatabase::load(&params)?;
+    // The store state after this block includes its own work
+    let chainwork = chainwork_store
+        .get_last(mappers::block::CHAINWORK_KEY)
+        .and_then(|value| utils::compact::bigint_to_u256(&value));
+    let block_data = mappers::block::extract_block_metrics(&block, &utxo_store, previous.as_ref(), &pools, chainwork)?;
     Ok(pb::bitcoin::analytics::v1::BlockMetricsRecord {
         table: "blocks".to_string(),
         block: Some(block_data),
     })
 }
 
+/// Accumulates the chain work of every block since genesis
+///
+/// Store handlers can't return errors. Bits that don't decode fail `map_block_metrics` with
+/// the same error, and skipping the block would corrupt every later chainwork, so it panics.
+#[substreams::handlers::store]
+pub fn store_chainwork(block: Block, store: StoreAddBigInt) {
+    let pow = mappers::block::proof_of_work(&block).expect("block bits encode a valid target");
+    store.add(0, mappers::block::CHAINWORK_KEY, utils::compact::u256_to_bigint(pow.work));
+}
+
+/// Maps blocks to their decoded coinbase transaction
+#[substreams::handlers::map]
+pub fn map_coinbase(block: Block) -> Result<pb::bitcoin::analytics::v1::CoinbaseInfoRecord, Error> {
+    let coinbase = mappers::coinbase::extract_coinbase_info(&block)?;
+    if coinbase.as_ref().is_some_and(|coinbase| coinbase.bip34_height_mismatch) {
+        substreams::log::info!("BIP34 height mismatch in the coinbase of block {}", block.height);
+    }
+    Ok(pb::bitcoin::analytics::v1::CoinbaseInfoRecord {
+        coinbase,
+    })
+}
+
+/// Recomputes the header hash, merkle root and witness commitment of blocks to detect corrupt
+/// upstream data, and checks their proof of work
+#[substreams::handlers::map]
+pub fn map_block_validation(block: Block) -> Result<pb::bitcoin::analytics::v1::BlockValidationRecord, Error> {
+    let validation = mappers::validation::validate_block(&block);
+    if !validation.valid {
+        substreams::log::info!("Block {} failed validation: {}", block.height, validation.mismatches.join(", "));
+    }
+    Ok(pb::bitcoin::analytics::v1::BlockValidationRecord {
+        validation: Some(validation),
+    })
+}
+
+/// Keeps the set of unspent outputs, keyed by `txid:vout`
+#[substreams::handlers::store]
+pub fn store_utxos(block: Block, store: StoreSetProto<UTXO>) {
+    mappers::utxo::update_utxo_store(&block, &store).expect("block output values are within the supply cap");
+}
+
 /// Maps blocks to UTXO records
 #[substreams::handlers::map]
-pub fn map_utxos(block: Block) -> Result<pb::bitcoin::utxo::v1::UTXORecords, Error> {
-    let records = mappers::utxo::process_utxos(&block)?;
+pub fn map_utxos(block: Block, utxo_store: StoreGetProto<UTXO>) -> Result<pb::bitcoin::utxo::v1::UTXORecords, Error> {
+    let records = mappers::utxo::process_utxos(&block, &utxo_store)?;
     Ok(pb::bitcoin::utxo::v1::UTXORecords {
         records,
     })
 }
+
+/// Maps blocks to their taproot outputs and spends, with the control block and annex of each spend
+#[substreams::handlers::map]
+pub fn map_taproot(
+    block: Block,
+    utxo_store: StoreGetProto<UTXO>,
+) -> Result<pb::bitcoin::analytics::v1::TaprootMetricsRecord, Error> {
+    let metrics = mappers::taproot::extract_taproot_metrics(&block, &utxo_store)?;
+    Ok(pb::bitcoin::analytics::v1::TaprootMetricsRecord {
+        metrics: Some(metrics),
+    })
+}
+
+/// Maps blocks to the ordinals inscriptions revealed in their tapscript witnesses
+#[substreams::handlers::map]
+pub fn map_inscriptions(block: Block) -> Result<InscriptionRecords, Error> {
+    Ok(InscriptionRecords {
+        records: mappers::inscriptions::extract_inscriptions(&block)?,
+    })
+}
+
+/// Maps blocks to the sat ranges of their outputs (ordinal theory) from the `start_height` param
+///
+/// Experimental, and not an ordinal index: a store can't read back its own previous values, so
+/// ranges are carried within each block only and sats of outputs created in earlier blocks are
+/// counted as unknown. As coinbase outputs mature after 100 blocks, the known ranges are mostly
+/// fresh subsidies.
+#[cfg(feature = "sat-ranges")]
+#[substreams::handlers::map]
+pub fn map_sat_ranges(
+    params: String,
+    block: Block,
+    utxo_store: StoreGetProto<UTXO>,
+) -> Result<pb::bitcoin::analytics::v1::SatRangeRecords, Error> {
+    if (block.height as u64) < mappers::sats::start_height(&params)? {
+        return Ok(Default::default());
+    }
+    let mut carried = std::collections::HashMap::new();
+    Ok(pb::bitcoin::analytics::v1::SatRangeRecords {
+        records: mappers::sats::assign_sat_ranges(&block, &utxo_store, &mut carried)?,
+    })
+}
+
+/// Maps blocks to per-transaction metrics
+#[substreams::handlers::map]
+pub fn map_transaction_metrics(
+    block: Block,
+    utxo_store: StoreGetProto<UTXO>,
+) -> Result<pb::bitcoin::analytics::v1::TransactionMetricsRecords, Error> {
+    let records = mappers::transaction::extract_transaction_metrics(&block, &utxo_store)?;
+    Ok(pb::bitcoin::analytics::v1::TransactionMetricsRecords {
+        records,
+    })
+}
+
+/// Maps blocks to address activity events, `params` holding mining pool overrides
+#[substreams::handlers::map]
+pub fn map_address_activity(
+    params: String,
+    block: Block,
+    utxo_store: StoreGetProto<UTXO>,
+) -> Result<pb::bitcoin::analytics::v1::AddressActivityRecords, Error> {
+    let pools = utils::pools::PoolDatabase::load(&params)?;
+    let records = mappers::address::extract_address_activity(&block, &utxo_store, &pools)?;
+    Ok(pb::bitcoin::analytics::v1::AddressActivityRecords {
+        records,
+    })
+}
+
+/// Keeps the running UTC day, which never goes backwards even when block timestamps do
+#[substreams::handlers::store]
+pub fn store_daily_date(block: Block, store: StoreMaxInt64) {
+    store.max(0, mappers::daily::CURRENT_DATE_KEY, mappers::daily::day_start(block.time));
+}
+
+/// Records the addresses active on the running UTC day, keyed by `date:address`
+#[substreams::handlers::store]
+pub fn store_daily_active_addresses(
+    block: Block,
+    utxo_store: StoreGetProto<UTXO>,
+    date_store: StoreGetInt64,
+    store: StoreSetIfNotExistsInt64,
+) {
+    let (finished_date, date) = mappers::daily::day_rollover(&date_store);
+    if let Some(finished_date) = finished_date {
+        store.delete_prefix(0, &mappers::daily::daily_prefix(finished_date));
+    }
+
+    let pools = utils::pools::PoolDatabase::default();
+    let activities = mappers::address::extract_address_activity(&block, &utxo_store, &pools)
+        .expect("block txids are valid hex and output values are within the supply cap");
+    for activity in activities.iter().filter_map(|record| record.activity.as_ref()) {
+        store.set_if_not_exists(1, mappers::daily::daily_key(date, &activity.address), &1);
+    }
+}
+
+/// Accumulates the integer totals of the running UTC day, keyed by `date:field`
+#[substreams::handlers::store]
+pub fn store_daily_totals(
+    block: Block,
+    utxo_store: StoreGetProto<UTXO>,
+    timestamp_store: StoreGetProto<BlockTimestamp>,
+    date_store: StoreGetInt64,
+    address_deltas: Deltas<DeltaInt64>,
+    store: StoreAddInt64,
+) {
+    let (finished_date, date) = mappers::daily::day_rollover(&date_store);
+    if let Some(finished_date) = finished_date {
+        store.delete_prefix(0, &mappers::daily::daily_prefix(finished_date));
+    }
+
+    let previous = timestamp_store.get_first(mappers::block::LAST_BLOCK_KEY);
+    let totals = mappers::daily::block_daily_totals(&block, &utxo_store, previous.as_ref())
+        .expect("block txids are valid hex and output values are within the supply cap");
+    for (field, value) in totals {
+        store.add(1, mappers::daily::daily_key(date, field), value);
+    }
+
+    // Every address first seen today creates a key in store_daily_active_addresses
+    let new_addresses = address_deltas
+        .deltas
+        .iter()
+        .filter(|delta| delta.operation == Operation::Create)
+        .count();
+    store.add(1, mappers::daily::daily_key(date, mappers::daily::ACTIVE_ADDRESSES), new_addresses as i64);
+}
+
+/// Accumulates the difficulty of the running UTC day, keyed by `date:difficulty`
+#[substreams::handlers::store]
+pub fn store_daily_difficulty(block: Block, date_store: StoreGetInt64, store: StoreAddFloat64) {
+    let (finished_date, date) = mappers::daily::day_rollover(&date_store);
+    if let Some(finished_date) = finished_date {
+        store.delete_prefix(0, &mappers::daily::daily_prefix(finished_date));
+    }
+    store.add(1, mappers::daily::daily_key(date, mappers::daily::DIFFICULTY), block.difficulty);
+}
+
+/// Emits the metrics of a UTC day once the first block of a later day is seen
+#[substreams::handlers::map]
+pub fn map_daily_metrics(
+    date_store: StoreGetInt64,
+    totals_store: StoreGetInt64,
+    difficulty_store: StoreGetFloat64,
+) -> Result<pb::bitcoin::analytics::v1::NetworkDailyMetricsRecords, Error> {
+    let mut records = Vec::new();
+
+    // Totals of the finished day are read as they were before this block pruned them
+    if let (Some(date), _) = mappers::daily::day_rollover(&date_store) {
+        let total = |field: &str| totals_store.get_first(mappers::daily::daily_key(date, field)).unwrap_or(0);
+        let difficulty_sum = difficulty_store
+            .get_first(mappers::daily::daily_key(date, mappers::daily::DIFFICULTY))
+            .unwrap_or(0.0);
+        records.push(pb::bitcoin::analytics::v1::NetworkDailyMetricsRecord {
+            table: "bitcoin_daily_stats".to_string(),
+            metrics: Some(mappers::daily::finalize_day(date, total, difficulty_sum)),
+        });
+    }
+
+    Ok(pb::bitcoin::analytics::v1::NetworkDailyMetricsRecords {
+        records,
+    })
+}
+
+/// Keeps the first and last block of the running difficulty epoch, keyed by `epoch:field`
+#[substreams::handlers::store]
+pub fn store_epoch_boundaries(block: Block, store: StoreSetProto<EpochBoundary>) {
+    let height = block.height as u64;
+    let Some(field) = mappers::epoch::boundary_field(height) else {
+        return;
+    };
+    let epoch = mappers::epoch::epoch_of(height);
+    if field == mappers::epoch::FIRST_BLOCK && epoch > 0 {
+        store.delete_prefix(0, &mappers::epoch::epoch_prefix(epoch - 1));
+    }
+    let boundary = mappers::epoch::extract_epoch_boundary(&block).expect("block bits are valid hex");
+    store.set(1, mappers::epoch::epoch_key(epoch, field), &boundary);
+}
+
+/// Emits the retarget metrics of a difficulty epoch at the first block of the next one
+#[substreams::handlers::map]
+pub fn map_epoch_metrics(
+    block: Block,
+    epoch_store: StoreGetProto<EpochBoundary>,
+) -> Result<EpochMetricsRecords, Error> {
+    let mut records = Vec::new();
+
+    // Boundaries of the finished epoch are read as they were before this block pruned them
+    let height = block.height as u64;
+    if height > 0 && mappers::epoch::boundary_field(height) == Some(mappers::epoch::FIRST_BLOCK) {
+        let epoch = mappers::epoch::epoch_of(height) - 1;
+        let first = epoch_store.get_first(mappers::epoch::epoch_key(epoch, mappers::epoch::FIRST_BLOCK));
+        let last = epoch_store.get_first(mappers::epoch::epoch_key(epoch, mappers::epoch::LAST_BLOCK));
+        if let (Some(first), Some(last)) = (first, last) {
+            records.push(pb::bitcoin::analytics::v1::EpochMetricsRecord {
+                table: "bitcoin_difficulty_epochs".to_string(),
+                metrics: Some(mappers::epoch::epoch_metrics(&first, &last, &block)?),
+            });
+        }
+    }
+
+    Ok(EpochMetricsRecords {
+        records,
+    })
+}
+
+/// Counts the blocks signaling each deployment per period, keyed by `deployment:period:signals`
+#[substreams::handlers::store]
+pub fn store_deployment_signals(params: String, block: Block, store: StoreAddInt64) {
+    let deployments = utils::deployments::Deployments::load(&params).expect("deployment params are valid");
+    let height = block.height as u64;
+    for deployment in &deployments.deployments {
+        let period = mappers::versionbits::period_of(height, deployment);
+        // Counts of the period before the one that just ended are no longer read
+        if mappers::versionbits::is_period_start(height, deployment) && period >= 2 {
+            store.delete_prefix(0, &mappers::versionbits::period_prefix(deployment, period - 2));
+        }
+        if mappers::versionbits::signals(block.version, deployment) {
+            store.add(1, mappers::versionbits::signal_key(deployment, period), 1);
+        }
+    }
+}
+
+/// Records the period in which each deployment started, keyed by deployment name
+#[substreams::handlers::store]
+pub fn store_deployment_starts(
+    params: String,
+    block: Block,
+    timestamp_store: StoreGetProto<BlockTimestamp>,
+    store: StoreSetIfNotExistsInt64,
+) {
+    let deployments = utils::deployments::Deployments::load(&params).expect("deployment params are valid");
+    let height = block.height as u64;
+    let previous = timestamp_store.get_first(mappers::block::LAST_BLOCK_KEY);
+    let median_time = mappers::versionbits::parent_median_time(&block, previous.as_ref());
+    for deployment in &deployments.deployments {
+        if mappers::versionbits::is_period_start(height, deployment)
+            && mappers::versionbits::has_started(deployment, height, median_time)
+        {
+            let period = mappers::versionbits::period_of(height, deployment);
+            store.set_if_not_exists(0, &deployment.name, &(period as i64));
+        }
+    }
+}
+
+/// Records the first LOCKED_IN or FAILED outcome of each deployment, keyed by deployment name
+#[substreams::handlers::store]
+pub fn store_deployment_outcomes(
+    params: String,
+    block: Block,
+    timestamp_store: StoreGetProto<BlockTimestamp>,
+    starts_store: StoreGetInt64,
+    signals_store: StoreGetInt64,
+    store: StoreSetIfNotExistsProto<DeploymentOutcome>,
+) {
+    let deployments = utils::deployments::Deployments::load(&params).expect("deployment params are valid");
+    let height = block.height as u64;
+    let previous = timestamp_store.get_first(mappers::block::LAST_BLOCK_KEY);
+    let median_time = mappers::versionbits::parent_median_time(&block, previous.as_ref());
+    for deployment in &deployments.deployments {
+        let period = mappers::versionbits::period_of(height, deployment);
+        if !mappers::versionbits::is_period_start(height, deployment) || period == 0 {
+            continue;
+        }
+        let started = starts_store.get_last(&deployment.name).map(|period| period as u64);
+        let signal_count = signals_store
+            .get_last(mappers::versionbits::signal_key(deployment, period - 1))
+            .unwrap_or(0);
+        if let Some(outcome) =
+            mappers::versionbits::decide_outcome(deployment, period, started, signal_count, median_time)
+        {
+            store.set_if_not_exists(0, &deployment.name, &outcome);
+        }
+    }
+}
+
+/// Emits the signaling count and state transition of each deployment at the end of every period
+#[substreams::handlers::map]
+pub fn map_deployments(
+    params: String,
+    block: Block,
+    starts_store: StoreGetInt64,
+    outcomes_store: StoreGetProto<DeploymentOutcome>,
+    signals_store: StoreGetInt64,
+) -> Result<DeploymentPeriodRecords, Error> {
+    let deployments = utils::deployments::Deployments::load(&params)?;
+    let height = block.height as u64;
+    let mut records = Vec::new();
+
+    for deployment in &deployments.deployments {
+        let period = mappers::versionbits::period_of(height, deployment);
+        if !mappers::versionbits::is_period_start(height, deployment) || period == 0 {
+            continue;
+        }
+        let started = starts_store.get_last(&deployment.name).map(|period| period as u64);
+        let outcome = outcomes_store.get_last(&deployment.name);
+        let signal_count = signals_store
+            .get_last(mappers::versionbits::signal_key(deployment, period - 1))
+            .unwrap_or(0);
+        records.push(pb::bitcoin::analytics::v1::DeploymentPeriodRecord {
+            table: "bitcoin_deployment_periods".to_string(),
+            period: Some(mappers::versionbits::deployment_period(
+                deployment,
+                period - 1,
+                signal_count,
+                started,
+                outcome.as_ref(),
+            )),
+        });
+    }
+
+    Ok(DeploymentPeriodRecords {
+        records,
+    })
+}
+
+/// Converts the records of a block to table changes for substreams-sink-sql
+#[substreams::handlers::map]
+pub fn db_out(
+    block_metrics: BlockMetricsRecord,
+    transaction_metrics: TransactionMetricsRecords,
+    utxos: UTXORecords,
+    address_activity: AddressActivityRecords,
+    daily_metrics: NetworkDailyMetricsRecords,
+    epoch_metrics: EpochMetricsRecords,
+    deployments: DeploymentPeriodRecords,
+    inscriptions: InscriptionRecords,
+) -> Result<substreams_database_change::pb::database::DatabaseChanges, Error> {
+    Ok(mappers::db::database_changes(
+        &block_metrics,
+        &transaction_metrics,
+        &utxos,
+        &address_activity,
+        &daily_metrics,
+        &epoch_metrics,
+        &deployments,
+        &inscriptions,
+    ))
+}
+
+/// Converts the records of a block to subgraph entity changes
+#[substreams::handlers::map]
+pub fn graph_out(
+    block_metrics: BlockMetricsRecord,
+    transaction_metrics: TransactionMetricsRecords,
+    utxos: UTXORecords,
+    address_activity: AddressActivityRecords,
+) -> Result<substreams_entity_change::pb::entity::EntityChanges, Error> {
+    Ok(mappers::graph::entity_changes(
+        &block_metrics,
+        &transaction_metrics,
+        &utxos,
+        &address_activity,
+    ))
+}
@@ -0,0 +1,74 @@
+use crate::mappers::utxo::{self, UtxoLookup};
+use crate::pb::bitcoin::analytics::v1::{AddressActivity, AddressActivityRecord};
+use crate::utils;
+use crate::utils::amount::Amount;
+use crate::utils::ids::Txid;
+use crate::utils::pools::PoolDatabase;
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+/// Extract one activity event per address touched by each input and output of a block
+///
+/// Spent outputs are resolved from the UTXO store; inputs and outputs without an
+/// address (coinbase, OP_RETURN, P2PK, ...) are skipped.
+pub fn extract_address_activity<S: UtxoLookup>(
+    block: &Block,
+    utxo_store: &S,
+    pools: &PoolDatabase,
+) -> Result<Vec<AddressActivityRecord>, Error> {
+    let mut records = Vec::new();
+    let block_number = block.height as u64;
+    let block_timestamp = block.time as u64;
+
+    let mut push = |address: String, tx_hash: &str, is_input: bool, io_index: usize, value: u64, script_type: String| {
+        let (address_tag, address_category) = match pools.payout_addresses.get(&address) {
+            Some(pool) => (pool.name.clone(), "MINING_POOL".to_string()),
+            None => (String::new(), String::new()),
+        };
+        records.push(AddressActivityRecord {
+            table: "bitcoin_address_transactions".to_string(),
+            activity: Some(AddressActivity {
+                address,
+                tx_hash: tx_hash.to_string(),
+                block_number,
+                block_timestamp,
+                is_input,
+                value,
+                script_type,
+                address_tag,
+                address_category,
+                io_index: io_index as u32,
+            }),
+        });
+    };
+
+    for (tx_idx, tx) in block.tx.iter().enumerate() {
+        let tx_hash = Txid::from_hex(&tx.txid)?.to_string();
+        for (vin_idx, prevout) in utxo::resolve_prevouts(tx, tx_idx, utxo_store).into_iter().enumerate() {
+            let Some(prevout) = prevout.filter(|prevout| !prevout.address.is_empty()) else {
+                continue;
+            };
+            push(prevout.address, &tx_hash, true, vin_idx, prevout.value, prevout.script_type);
+        }
+
+        for (vout_idx, vout) in tx.vout.iter().enumerate() {
+            let Some(script) = &vout.script_pub_key else {
+                continue;
+            };
+            let script_bytes = hex::decode(&script.hex).unwrap_or_default();
+            let Some(address) = utils::bitcoin_utils::extract_address_from_script(&script_bytes, false) else {
+                continue;
+            };
+            push(
+                address,
+                &tx_hash,
+                false,
+                vout_idx,
+                Amount::from_btc(vout.value)?.to_sat(),
+                utils::script::classify(&script_bytes).name(),
+            );
+        }
+    }
+
+    Ok(records)
+}
@@ -1,86 +1,280 @@
-use crate::pb::bitcoin::analytics::v1::BlockMetrics;
+use crate::mappers::spend::{self, SpendType};
+use crate::mappers::utxo::{self, UtxoLookup};
+use crate::mappers::versionbits;
+use crate::pb::bitcoin::analytics::v1::{BlockMetrics, BlockTimestamp};
 use crate::utils;
+use crate::utils::amount::Amount;
+use crate::utils::compact;
+use crate::utils::ids::BlockHash;
+use crate::utils::pools::{PoolDatabase, PoolMatch, PoolMatchMethod};
+use crate::utils::script::{self, ScriptType};
 use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+use primitive_types::U256;
 use substreams::errors::Error;
 
 // Constants for Bitcoin block rewards by halving epoch
-const INITIAL_BLOCK_REWARD: u64 = 50_0000_0000; // 50 BTC in satoshis
-const BLOCKS_PER_HALVING: u64 = 210_000;
+pub const INITIAL_BLOCK_REWARD: u64 = 50_0000_0000; // 50 BTC in satoshis
+pub const BLOCKS_PER_HALVING: u64 = 210_000;
 
-pub fn extract_block_metrics(block: &Block) -> Result<BlockMetrics, Error> {
-    // Extract miner info
-    let tx_count = block.tx.len() as usize;
-    
-    let miner = if tx_count > 0 && !block.tx.is_empty() {
-        // Get the first transaction (coinbase)
-        utils::extract_miner_name(&block.tx[0])
+/// Key under which `store_block_timestamps` keeps the latest block's timestamps
+pub const LAST_BLOCK_KEY: &str = "last_block";
+
+/// Key of the cumulative chain work in `store_chainwork`
+pub const CHAINWORK_KEY: &str = "chainwork";
+
+/// Extract the header timestamps of a block
+pub fn extract_block_timestamp(block: &Block) -> BlockTimestamp {
+    BlockTimestamp {
+        number: block.height as u64,
+        time: block.time,
+        median_time: block.mediantime,
+    }
+}
+
+/// Signed seconds elapsed since the parent block, 0 when `previous` isn't the parent
+///
+/// Header times only have to exceed the median time past, so the interval can be negative.
+pub fn block_interval(block: &Block, previous: Option<&BlockTimestamp>) -> i64 {
+    previous
+        .filter(|prev| prev.number + 1 == block.height as u64)
+        .map_or(0, |prev| block.time - prev.time)
+}
+
+/// Subsidy of the block at `height` in satoshis, halved every 210,000 blocks
+pub fn block_subsidy(height: u64) -> u64 {
+    let halvings = height / BLOCKS_PER_HALVING;
+    if halvings >= 64 {
+        // After 64 halvings, the reward is effectively 0
+        0
     } else {
-        "Unknown".to_string()
-    };
+        INITIAL_BLOCK_REWARD >> halvings
+    }
+}
+
+/// Proof of work of a block, decoded from its compact `bits`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofOfWork {
+    pub bits: u32,
+    pub target: U256,
+    /// Expected number of hashes to meet the target
+    pub work: U256,
+}
+
+/// Decode the compact target of a block; work is exact 256-bit integer arithmetic
+pub fn proof_of_work(block: &Block) -> Result<ProofOfWork, Error> {
+    let bits = compact::parse_bits(&block.bits)?;
+    let target = compact::decode_compact(bits)?;
+    Ok(ProofOfWork {
+        bits,
+        target,
+        work: compact::block_work(target),
+    })
+}
+
+/// SegWit and Taproot usage across the transactions, inputs and outputs of a block
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WitnessAdoption {
+    /// Transactions carrying witness data, the coinbase included
+    pub segwit_txs: u32,
+    /// Transactions with at least one P2SH-wrapped witness input
+    pub nested_segwit_txs: u32,
+    /// Transactions spending or creating a taproot output
+    pub taproot_txs: u32,
+    /// Non-coinbase inputs and how many of them spend witness or taproot outputs
+    pub inputs: u32,
+    pub segwit_inputs: u32,
+    pub nested_segwit_inputs: u32,
+    pub taproot_inputs: u32,
+    /// Outputs and how many of them are witness programs or P2TR; nested outputs hide behind P2SH
+    pub outputs: u32,
+    pub segwit_outputs: u32,
+    pub taproot_outputs: u32,
+    /// Serialized witness bytes, the marker and flag included
+    pub witness_bytes: u64,
+}
+
+impl WitnessAdoption {
+    /// Weight the witness discount saved: witness bytes weigh 1 instead of 4 (BIP141)
+    pub fn weight_saved(&self) -> u64 {
+        self.witness_bytes * 3
+    }
+}
+
+/// Count the SegWit and Taproot transactions, inputs and outputs of a block
+///
+/// Inputs are classified from their scriptSig and witness like the `spend_type` of spent UTXOs.
+pub fn witness_adoption(block: &Block) -> Result<WitnessAdoption, Error> {
+    let mut adoption = WitnessAdoption::default();
 
-    // Count segwit and taproot transactions
-    let mut segwit_count = 0;
-    let mut taproot_count = 0;
-    
     for tx in &block.tx {
-        if utils::is_segwit_transaction(tx) {
-            segwit_count += 1;
+        let mut nested_segwit = false;
+        let mut taproot = false;
+
+        if !utils::bitcoin_utils::is_coinbase_transaction(tx) {
+            for vin in &tx.vin {
+                let spend_type = spend::classify_input(vin)?;
+                adoption.inputs += 1;
+                if spend_type.is_segwit() {
+                    adoption.segwit_inputs += 1;
+                }
+                if matches!(spend_type, SpendType::P2shP2wpkh | SpendType::P2shP2wsh) {
+                    adoption.nested_segwit_inputs += 1;
+                    nested_segwit = true;
+                }
+                if spend_type.is_taproot() {
+                    adoption.taproot_inputs += 1;
+                    taproot = true;
+                }
+            }
         }
-        if utils::is_taproot_transaction(tx) {
-            taproot_count += 1;
+
+        for script_pub_key in tx.vout.iter().filter_map(|vout| vout.script_pub_key.as_ref()) {
+            let script_bytes = hex::decode(&script_pub_key.hex).unwrap_or_default();
+            adoption.outputs += 1;
+            if script::witness_program(&script_bytes).is_some() {
+                adoption.segwit_outputs += 1;
+            }
+            if script::classify(&script_bytes) == ScriptType::P2tr {
+                adoption.taproot_outputs += 1;
+                taproot = true;
+            }
+        }
+
+        if utils::is_segwit_transaction(tx) {
+            adoption.segwit_txs += 1;
         }
+        adoption.nested_segwit_txs += nested_segwit as u32;
+        adoption.taproot_txs += taproot as u32;
+
+        // weight = 3 * stripped size + total size, so 4 * total size - weight = 3 * witness size
+        let size = tx.size.max(0) as u64;
+        adoption.witness_bytes += (size * 4).saturating_sub(tx.weight.max(0) as u64) / 3;
     }
 
-    // Calculate segwit and taproot percentages
-    let _segwit_percent = if tx_count > 0 {
-        segwit_count as f64 / tx_count as f64 * 100.0
+    Ok(adoption)
+}
+
+/// Share of `count` in `total`, in percent
+fn percent(count: u32, total: usize) -> f64 {
+    if total > 0 {
+        count as f64 / total as f64 * 100.0
     } else {
         0.0
-    };
+    }
+}
+
+/// Extract block metrics, `previous` being the timestamps of the parent block when known,
+/// `pools` the database used to attribute the block to a mining pool and `chainwork` the
+/// cumulative work up to this block when tracked from genesis
+pub fn extract_block_metrics<S: UtxoLookup>(
+    block: &Block,
+    utxo_store: &S,
+    previous: Option<&BlockTimestamp>,
+    pools: &PoolDatabase,
+    chainwork: Option<U256>,
+) -> Result<BlockMetrics, Error> {
+    // Extract miner info
+    let tx_count = block.tx.len();
     
-    let _taproot_percent = if tx_count > 0 {
-        taproot_count as f64 / tx_count as f64 * 100.0
-    } else {
-        0.0
+    let miner = match block.tx.first() {
+        // The first transaction is the coinbase
+        Some(coinbase_tx) => pools.identify(coinbase_tx),
+        None => PoolMatch {
+            name: "Unknown".to_string(),
+            method: PoolMatchMethod::Unknown,
+        },
     };
 
+    // Count segwit and taproot transactions, inputs and outputs
+    let adoption = witness_adoption(block)?;
+
     // Calculate block reward based on halving schedule
-    let block_height = block.height as u64;
-    let halvings = block_height / BLOCKS_PER_HALVING;
-    let block_reward = if halvings >= 64 {
-        // After 64 halvings, the reward is effectively 0
-        0
-    } else {
-        INITIAL_BLOCK_REWARD >> halvings
-    };
+    let block_reward = block_subsidy(block.height as u64);
+
+    // Calculate block time (in seconds) from the parent block's timestamp
+    let block_interval = block_interval(block, previous);
+    let block_time = block_interval.clamp(0, u32::MAX as i64) as u32;
+
+    // Calculate total fees as the sum of inputs minus outputs of every non-coinbase
+    // transaction, with spent outputs resolved from the UTXO store
+    let mut input_fees = Some(0u64);
+    for (tx_idx, tx) in block.tx.iter().enumerate() {
+        if utils::bitcoin_utils::is_coinbase_transaction(tx) {
+            continue;
+        }
+        let prevouts = utxo::resolve_prevouts(tx, tx_idx, utxo_store);
+        input_fees = input_fees.zip(utxo::transaction_fee(tx, &prevouts)).map(|(total, fee)| total + fee);
+    }
 
-    // Calculate block time (in seconds)
-    // In a real implementation, we would compare with the previous block's timestamp
-    // For now, we'll use a placeholder of 10 minutes (600 seconds)
-    let block_time = 600;
+    // Cross-check: the coinbase claims the subsidy plus the fees (miners may claim less)
+    let coinbase_fees = block
+        .tx
+        .iter()
+        .find(|tx| utils::bitcoin_utils::is_coinbase_transaction(tx))
+        .map(utils::bitcoin_utils::transaction_output_value)
+        .transpose()?
+        .map_or(0, |output_value| output_value.saturating_sub(Amount::from_sat(block_reward)).to_sat());
+
+    // Leave the total unset when the store can't resolve every input, coinbase_fees stays available
+    let fees_resolved = input_fees.is_some();
+    if !fees_resolved {
+        substreams::log::info!("Unresolved inputs in block {}, total fees left unset", block.height);
+    }
+    let total_fees = input_fees.unwrap_or_default();
 
-    // Calculate total fees
-    // In a real implementation, we would calculate the difference between
-    // inputs and outputs for each transaction
-    // For now, we'll use a placeholder
-    let total_fees = 0;
+    let pow = proof_of_work(block)?;
+
+    // Cross-check the tracked chain work against the node's
+    if let (Some(chainwork), Ok(reported)) = (chainwork, compact::parse_u256(&block.chainwork)) {
+        if chainwork != reported {
+            substreams::log::info!(
+                "Chainwork mismatch at block {}: tracked {}, reported {}",
+                block.height,
+                compact::to_hex_u256(chainwork),
+                block.chainwork
+            );
+        }
+    }
 
     // Create BlockMetrics
     let block_metrics = BlockMetrics {
         number: block.height as u64,
-        hash: utils::to_hex_string(&block.hash),
+        hash: BlockHash::from_hex(&block.hash)?.to_string(),
         timestamp: block.time as u64,
         size: block.size as u32,
         weight: block.weight as u32,
         tx_count: tx_count as u32,
         difficulty: block.difficulty,
-        miner,
-        block_time: block_time,
-        total_fees: total_fees,
-        block_reward: block_reward,
+        miner: miner.name,
+        miner_match_method: miner.method.as_str().to_string(),
+        block_time,
+        total_fees,
+        fees_resolved,
+        coinbase_fees,
+        block_reward,
         version: block.version as u32,
-        nonce: block.nonce as u32,
-        bits: block.bits.parse::<u32>().unwrap_or(0),
+        nonce: block.nonce,
+        bits: pow.bits,
+        block_interval,
+        median_time: block.mediantime as u64,
+        target: compact::to_hex_u256(pow.target),
+        work: compact::to_hex_u256(pow.work),
+        chainwork: chainwork.map(compact::to_hex_u256).unwrap_or_default(),
+        version_bits: versionbits::signaled_bits(block.version),
+        segwit_percent: percent(adoption.segwit_txs, tx_count),
+        taproot_percent: percent(adoption.taproot_txs, tx_count),
+        segwit_tx_count: adoption.segwit_txs,
+        nested_segwit_tx_count: adoption.nested_segwit_txs,
+        taproot_tx_count: adoption.taproot_txs,
+        input_count: adoption.inputs,
+        segwit_input_count: adoption.segwit_inputs,
+        nested_segwit_input_count: adoption.nested_segwit_inputs,
+        taproot_input_count: adoption.taproot_inputs,
+        output_count: adoption.outputs,
+        segwit_output_count: adoption.segwit_outputs,
+        taproot_output_count: adoption.taproot_outputs,
+        witness_bytes: adoption.witness_bytes,
+        witness_weight_saved: adoption.weight_saved(),
     };
 
     Ok(block_metrics)
@@ -0,0 +1,132 @@
+use crate::pb::bitcoin::analytics::v1::{CoinbaseInfo, MergedMiningCommitment};
+use crate::utils::bitcoin_utils;
+use crate::utils::ids::Txid;
+use crate::utils::script::{self, Instruction};
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, Transaction};
+
+/// First mainnet block whose coinbase must start with its height (BIP34)
+pub const BIP34_HEIGHT: i64 = 227_931;
+
+/// Shortest run of printable ASCII reported as a tag
+const MIN_TAG_LEN: usize = 4;
+
+/// Start of the witness commitment output: OP_RETURN, a 36-byte push and the 0xaa21a9ed header (BIP141)
+const WITNESS_COMMITMENT_HEADER: [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+
+/// Marker of the RSK merged-mining commitment, followed by the 32-byte RSK block hash
+const RSK_TAG: &[u8] = b"RSKBLOCK:";
+
+/// Magic of an AuxPoW commitment in the coinbase scriptSig, followed by the 32-byte merkle root
+/// of the merged-mined chains, the merkle tree size and a nonce (both 4 bytes little-endian)
+const AUXPOW_MAGIC: [u8; 4] = [0xfa, 0xbe, 0x6d, 0x6d];
+
+/// Decode the coinbase transaction of a block, `None` when the block has none
+pub fn extract_coinbase_info(block: &Block) -> Result<Option<CoinbaseInfo>, Error> {
+    let Some(tx) = block.tx.first().filter(|tx| bitcoin_utils::is_coinbase_transaction(tx)) else {
+        return Ok(None);
+    };
+    let script_sig = hex::decode(&tx.vin[0].coinbase)
+        .map_err(|_| Error::msg(format!("Invalid coinbase scriptSig in block {}", block.height)))?;
+
+    // The height comes first (BIP34), usually followed by the extranonce. Tags are read from the
+    // raw bytes after the height, as pools often write text that doesn't parse as pushes.
+    let mut instructions = script::instructions(&script_sig);
+    let tags_start = match instructions.next() {
+        Some(Ok(_)) => instructions.position(),
+        _ => 0,
+    };
+    let extranonce = match instructions.next() {
+        Some(Ok(Instruction::PushBytes(bytes))) => hex::encode(bytes),
+        _ => String::new(),
+    };
+    let bip34_height = bip34_height(&script_sig);
+
+    let mut merged_mining = Vec::new();
+    merged_mining.extend(auxpow_commitment(&script_sig));
+    merged_mining.extend(rsk_commitment(tx));
+
+    Ok(Some(CoinbaseInfo {
+        block_height: block.height as u64,
+        tx_id: Txid::from_hex(&tx.txid)?.to_string(),
+        script_sig: tx.vin[0].coinbase.clone(),
+        bip34_height: bip34_height.unwrap_or(-1),
+        bip34_height_mismatch: block.height >= BIP34_HEIGHT && bip34_height != Some(block.height),
+        extranonce,
+        tags: ascii_tags(&script_sig[tags_start..]),
+        witness_commitment: witness_commitment(tx).map(hex::encode).unwrap_or_default(),
+        witness_reserved_value: tx.vin[0]
+            .txinwitness
+            .first()
+            .filter(|item| item.len() == 64)
+            .cloned()
+            .unwrap_or_default(),
+        merged_mining,
+    }))
+}
+
+/// Height pushed first in a coinbase scriptSig (BIP34), as a minimal script number
+pub fn bip34_height(script_sig: &[u8]) -> Option<i64> {
+    match script::instructions(script_sig).next()?.ok()? {
+        Instruction::PushBytes(bytes) => script::read_script_num(bytes, true).filter(|height| *height >= 0),
+        Instruction::Op(opcode) => script::small_int_value(opcode).map(i64::from),
+    }
+}
+
+/// Runs of at least 4 printable ASCII characters, where pools put their tags
+pub fn ascii_tags(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .split(|byte| !(0x20..=0x7e).contains(byte))
+        .map(|run| String::from_utf8_lossy(run).trim().to_string())
+        .filter(|tag| tag.len() >= MIN_TAG_LEN)
+        .collect()
+}
+
+/// Witness commitment of the coinbase (BIP141), from the last output carrying one
+pub fn witness_commitment(tx: &Transaction) -> Option<Vec<u8>> {
+    tx.vout
+        .iter()
+        .rev()
+        .filter_map(|vout| vout.script_pub_key.as_ref())
+        .filter_map(|script| hex::decode(&script.hex).ok())
+        .find(|script| script.len() >= 38 && script.starts_with(&WITNESS_COMMITMENT_HEADER))
+        .map(|script| script[6..38].to_vec())
+}
+
+/// AuxPoW commitment in the coinbase scriptSig, used by Namecoin, Syscoin and other merged-mined
+/// chains; the chains share one merkle root, so they can't be told apart from the parent block
+pub fn auxpow_commitment(script_sig: &[u8]) -> Option<MergedMiningCommitment> {
+    let start = script_sig.windows(AUXPOW_MAGIC.len()).position(|window| window == AUXPOW_MAGIC)?;
+    let payload = &script_sig[start + AUXPOW_MAGIC.len()..];
+    let root = payload.get(..32)?;
+    let read_u32 = |offset: usize| {
+        payload
+            .get(offset..offset + 4)
+            .map_or(0, |bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    Some(MergedMiningCommitment {
+        protocol: "AUXPOW".to_string(),
+        hash: hex::encode(root),
+        output_index: -1,
+        merkle_size: read_u32(32),
+        nonce: read_u32(36),
+    })
+}
+
+/// RSK merged-mining commitment, from the last OP_RETURN output tagged `RSKBLOCK:`
+pub fn rsk_commitment(tx: &Transaction) -> Option<MergedMiningCommitment> {
+    tx.vout.iter().enumerate().rev().find_map(|(index, vout)| {
+        let script = hex::decode(&vout.script_pub_key.as_ref()?.hex).ok()?;
+        if script.first() != Some(&script::OP_RETURN) {
+            return None;
+        }
+        let start = script.windows(RSK_TAG.len()).position(|window| window == RSK_TAG)?;
+        let hash = script.get(start + RSK_TAG.len()..start + RSK_TAG.len() + 32)?;
+        Some(MergedMiningCommitment {
+            protocol: "RSK".to_string(),
+            hash: hex::encode(hash),
+            output_index: index as i32,
+            ..Default::default()
+        })
+    })
+}
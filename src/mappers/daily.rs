@@ -0,0 +1,146 @@
+use crate::mappers::block;
+use crate::mappers::transaction;
+use crate::mappers::utxo::UtxoLookup;
+use crate::pb::bitcoin::analytics::v1::{BlockTimestamp, NetworkDailyMetrics};
+use crate::utils;
+use substreams::errors::Error;
+use substreams::store::{StoreGet, StoreGetInt64};
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Key of the running UTC day in `store_daily_date`
+pub const CURRENT_DATE_KEY: &str = "date";
+
+// Fields of the integer daily totals kept in `store_daily_totals`
+pub const BLOCK_COUNT: &str = "block_count";
+pub const BLOCK_INTERVAL: &str = "block_interval";
+pub const BLOCK_SIZE: &str = "block_size";
+pub const TX_COUNT: &str = "tx_count";
+pub const TX_VOLUME: &str = "tx_volume";
+pub const FEES: &str = "fees";
+pub const VSIZE: &str = "vsize";
+pub const SEGWIT_TX_COUNT: &str = "segwit_tx_count";
+pub const TAPROOT_TX_COUNT: &str = "taproot_tx_count";
+pub const ACTIVE_ADDRESSES: &str = "active_addresses";
+
+/// Field of the difficulty sum kept in `store_daily_difficulty`
+pub const DIFFICULTY: &str = "difficulty";
+
+/// Start of the UTC day containing `timestamp`, in seconds since the epoch
+pub fn day_start(timestamp: i64) -> i64 {
+    timestamp.div_euclid(SECONDS_PER_DAY) * SECONDS_PER_DAY
+}
+
+/// Key of a daily aggregate
+pub fn daily_key(date: i64, field: &str) -> String {
+    format!("{}:{}", date, field)
+}
+
+/// Prefix shared by every daily aggregate of `date`
+pub fn daily_prefix(date: i64) -> String {
+    format!("{}:", date)
+}
+
+/// Read the running UTC day before and after the current block from `store_daily_date`
+///
+/// The first value is set only when the block rolls the day over, and is the day to finalize.
+pub fn day_rollover(date_store: &StoreGetInt64) -> (Option<i64>, i64) {
+    let current = date_store.get_last(CURRENT_DATE_KEY).unwrap_or_default();
+    let previous = date_store
+        .get_first(CURRENT_DATE_KEY)
+        .filter(|previous| *previous < current);
+    (previous, current)
+}
+
+/// Contributions of a block to the integer daily totals
+pub fn block_daily_totals<S: UtxoLookup>(
+    block: &Block,
+    utxo_store: &S,
+    previous: Option<&BlockTimestamp>,
+) -> Result<Vec<(&'static str, i64)>, Error> {
+    let mut tx_volume = 0u64;
+    let mut fees = 0u64;
+    let mut vsize = 0u64;
+    let mut segwit_tx_count = 0i64;
+    let mut taproot_tx_count = 0i64;
+
+    let records = transaction::extract_transaction_metrics(block, utxo_store)?;
+    for (tx, record) in block.tx.iter().zip(&records) {
+        let Some(metrics) = &record.transaction else {
+            continue;
+        };
+        if metrics.has_witness {
+            segwit_tx_count += 1;
+        }
+        if utils::is_taproot_transaction(tx) {
+            taproot_tx_count += 1;
+        }
+        if !metrics.is_coinbase {
+            tx_volume += metrics.output_value;
+        }
+        // Only transactions with a known fee count towards the average fee rate
+        if !metrics.is_coinbase && metrics.inputs_resolved {
+            fees += metrics.fee;
+            vsize += transaction::transaction_vsize(tx);
+        }
+    }
+
+    Ok(vec![
+        (BLOCK_COUNT, 1),
+        (BLOCK_INTERVAL, block::block_interval(block, previous)),
+        (BLOCK_SIZE, block.size as i64),
+        (TX_COUNT, block.tx.len() as i64),
+        (TX_VOLUME, tx_volume as i64),
+        (FEES, fees as i64),
+        (VSIZE, vsize as i64),
+        (SEGWIT_TX_COUNT, segwit_tx_count),
+        (TAPROOT_TX_COUNT, taproot_tx_count),
+    ])
+}
+
+/// Build the metrics of a finished day from its totals, `total` reading an integer total by field
+pub fn finalize_day<F: Fn(&str) -> i64>(
+    date: i64,
+    total: F,
+    difficulty_sum: f64,
+) -> NetworkDailyMetrics {
+    let block_count = total(BLOCK_COUNT);
+    let tx_count = total(TX_COUNT);
+    let vsize = total(VSIZE);
+
+    let per_block = |value: f64| {
+        if block_count > 0 {
+            value / block_count as f64
+        } else {
+            0.0
+        }
+    };
+    let tx_percent = |count: i64| {
+        if tx_count > 0 {
+            count as f64 / tx_count as f64 * 100.0
+        } else {
+            0.0
+        }
+    };
+
+    NetworkDailyMetrics {
+        date: date as u64,
+        avg_block_time: per_block(total(BLOCK_INTERVAL) as f64),
+        total_tx_count: tx_count as u32,
+        total_tx_volume: total(TX_VOLUME) as u64,
+        avg_block_size: per_block(total(BLOCK_SIZE) as f64) as u32,
+        avg_tx_per_block: per_block(tx_count as f64),
+        avg_fee_rate: if vsize > 0 {
+            total(FEES) as f64 / vsize as f64
+        } else {
+            0.0
+        },
+        // The mempool isn't observable from blocks
+        mempool_tx_count: 0,
+        segwit_tx_percent: tx_percent(total(SEGWIT_TX_COUNT)),
+        taproot_tx_percent: tx_percent(total(TAPROOT_TX_COUNT)),
+        avg_difficulty: per_block(difficulty_sum),
+        active_addresses: total(ACTIVE_ADDRESSES) as u32,
+    }
+}
@@ -0,0 +1,294 @@
+use crate::pb::bitcoin::analytics::v1::{
+    AddressActivityRecords, BlockMetricsRecord, DeploymentPeriodRecords, EpochMetricsRecords, InscriptionRecords,
+    NetworkDailyMetricsRecords, TransactionMetricsRecords,
+};
+use crate::pb::bitcoin::utxo::v1::UTXORecords;
+use chrono::{DateTime, SecondsFormat};
+use substreams_database_change::pb::database::DatabaseChanges;
+use substreams_database_change::tables::{Tables, ToDatabaseValue};
+
+/// A Unix timestamp in seconds, written as an RFC 3339 UTC datetime
+struct Timestamp(u64);
+
+impl ToDatabaseValue for Timestamp {
+    fn to_value(self) -> String {
+        DateTime::from_timestamp(self.0 as i64, 0)
+            .unwrap_or_default()
+            .to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+}
+
+/// A Unix timestamp in seconds, written as its UTC calendar date
+struct Date(u64);
+
+impl ToDatabaseValue for Date {
+    fn to_value(self) -> String {
+        DateTime::from_timestamp(self.0 as i64, 0)
+            .unwrap_or_default()
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+}
+
+/// Convert the records of a block to the row changes applied by substreams-sink-sql
+///
+/// Column names follow `clickhouse_schema.sql`; table names are taken from the records.
+/// Booleans are written as `u8`, as they are stored as UInt8 in the ClickHouse schema and
+/// Postgres accepts '1' and '0'. Changes to the same row within a block are merged.
+#[allow(clippy::too_many_arguments)]
+pub fn database_changes(
+    block: &BlockMetricsRecord,
+    transactions: &TransactionMetricsRecords,
+    utxos: &UTXORecords,
+    activities: &AddressActivityRecords,
+    daily: &NetworkDailyMetricsRecords,
+    epochs: &EpochMetricsRecords,
+    deployments: &DeploymentPeriodRecords,
+    inscriptions: &InscriptionRecords,
+) -> DatabaseChanges {
+    let mut tables = Tables::new();
+    block_changes(&mut tables, block);
+    transaction_changes(&mut tables, transactions);
+    utxo_changes(&mut tables, utxos);
+    address_activity_changes(&mut tables, activities);
+    daily_changes(&mut tables, daily);
+    epoch_changes(&mut tables, epochs);
+    deployment_changes(&mut tables, deployments);
+    inscription_changes(&mut tables, inscriptions);
+    tables.to_database_changes()
+}
+
+fn block_changes(tables: &mut Tables, record: &BlockMetricsRecord) {
+    let Some(block) = &record.block else {
+        return;
+    };
+    tables
+        .create_row(&record.table, block.number.to_string())
+        .set("number", block.number)
+        .set("hash", &block.hash)
+        .set("timestamp", Timestamp(block.timestamp))
+        .set("size", block.size)
+        .set("weight", block.weight)
+        .set("tx_count", block.tx_count)
+        .set("difficulty", block.difficulty.to_string())
+        .set("miner", &block.miner)
+        .set("miner_match_method", &block.miner_match_method)
+        .set("block_time", block.block_time)
+        .set("block_interval", block.block_interval)
+        .set("median_time", Timestamp(block.median_time))
+        .set("total_fees", block.total_fees)
+        .set("fees_resolved", block.fees_resolved as u8)
+        .set("coinbase_fees", block.coinbase_fees)
+        .set("block_reward", block.block_reward)
+        .set("version", block.version)
+        .set("nonce", block.nonce)
+        .set("bits", block.bits)
+        .set("target", &block.target)
+        .set("work", &block.work)
+        .set("chainwork", &block.chainwork)
+        .set("version_bits", block.version_bits)
+        .set("segwit_percent", block.segwit_percent.to_string())
+        .set("taproot_percent", block.taproot_percent.to_string())
+        .set("segwit_tx_count", block.segwit_tx_count)
+        .set("nested_segwit_tx_count", block.nested_segwit_tx_count)
+        .set("taproot_tx_count", block.taproot_tx_count)
+        .set("input_count", block.input_count)
+        .set("segwit_input_count", block.segwit_input_count)
+        .set("nested_segwit_input_count", block.nested_segwit_input_count)
+        .set("taproot_input_count", block.taproot_input_count)
+        .set("output_count", block.output_count)
+        .set("segwit_output_count", block.segwit_output_count)
+        .set("taproot_output_count", block.taproot_output_count)
+        .set("witness_bytes", block.witness_bytes)
+        .set("witness_weight_saved", block.witness_weight_saved);
+}
+
+fn transaction_changes(tables: &mut Tables, records: &TransactionMetricsRecords) {
+    for record in &records.records {
+        let Some(tx) = &record.transaction else {
+            continue;
+        };
+        // Two early coinbases share their txid (BIP30), so the height is part of the key
+        tables
+            .create_row(
+                &record.table,
+                [("tx_id", tx.hash.clone()), ("block_height", tx.block_number.to_string())],
+            )
+            .set("tx_id", &tx.hash)
+            .set("wtxid", &tx.wtxid)
+            .set("block_height", tx.block_number)
+            .set("block_time", Timestamp(tx.block_timestamp))
+            .set("fee", tx.fee)
+            .set("fee_rate", tx.fee_rate.to_string())
+            .set("size", tx.size)
+            .set("weight", tx.weight)
+            .set("is_coinbase", tx.is_coinbase as u8)
+            .set("has_witness", tx.has_witness as u8)
+            .set("tx_type", &tx.tx_type)
+            .set("input_count", tx.input_count)
+            .set("output_count", tx.output_count)
+            .set("total_input_value", tx.input_value)
+            .set("total_output_value", tx.output_value)
+            .set("inputs_resolved", tx.inputs_resolved as u8);
+    }
+}
+
+fn utxo_changes(tables: &mut Tables, records: &UTXORecords) {
+    for record in &records.records {
+        let Some(utxo) = &record.utxo else {
+            continue;
+        };
+        let key = [("tx_id", utxo.tx_id.clone()), ("vout_index", utxo.vout_index.to_string())];
+        // Spends carry the whole resolved output, so the row stays complete on insert-only sinks
+        let row = if utxo.is_spent {
+            tables.update_row(&record.table, key)
+        } else {
+            tables.create_row(&record.table, key)
+        };
+        row.set("tx_id", &utxo.tx_id)
+            .set("vout_index", utxo.vout_index)
+            .set("value", utxo.value)
+            .set("script_type", &utxo.script_type)
+            .set("multisig_required", utxo.multisig_required)
+            .set("multisig_total", utxo.multisig_total)
+            .set("address", &utxo.address)
+            .set("block_height", utxo.block_height)
+            .set("block_time", Timestamp(utxo.block_time))
+            .set("is_spent", utxo.is_spent as u8)
+            .set("spent_in_tx_id", &utxo.spent_in_tx_id)
+            .set("spent_in_input_index", utxo.spent_in_input_index)
+            .set("spent_at_block_height", utxo.spent_at_block_height)
+            .set("spent_at_block_time", Timestamp(utxo.spent_at_block_time))
+            .set("spend_type", &utxo.spend_type);
+    }
+}
+
+fn address_activity_changes(tables: &mut Tables, records: &AddressActivityRecords) {
+    for record in &records.records {
+        let Some(activity) = &record.activity else {
+            continue;
+        };
+        tables
+            .create_row(
+                &record.table,
+                // Two early coinbases share their txid (BIP30)
+                [
+                    ("tx_id", activity.tx_hash.clone()),
+                    ("block_height", activity.block_number.to_string()),
+                    ("is_input", (activity.is_input as u8).to_string()),
+                    ("io_index", activity.io_index.to_string()),
+                ],
+            )
+            .set("address", &activity.address)
+            .set("tx_id", &activity.tx_hash)
+            .set("block_height", activity.block_number)
+            .set("block_time", Timestamp(activity.block_timestamp))
+            .set("is_input", activity.is_input as u8)
+            .set("io_index", activity.io_index)
+            .set("value", activity.value)
+            .set("script_type", &activity.script_type)
+            .set("address_tag", &activity.address_tag)
+            .set("address_category", &activity.address_category);
+    }
+}
+
+fn daily_changes(tables: &mut Tables, records: &NetworkDailyMetricsRecords) {
+    for record in &records.records {
+        let Some(metrics) = &record.metrics else {
+            continue;
+        };
+        tables
+            .create_row(&record.table, Date(metrics.date).to_value())
+            .set("date", Date(metrics.date))
+            .set("avg_block_time", metrics.avg_block_time.to_string())
+            .set("total_tx_count", metrics.total_tx_count)
+            .set("total_tx_volume", metrics.total_tx_volume)
+            .set("avg_block_size", metrics.avg_block_size)
+            .set("avg_tx_per_block", metrics.avg_tx_per_block.to_string())
+            .set("avg_fee_rate", metrics.avg_fee_rate.to_string())
+            .set("mempool_tx_count", metrics.mempool_tx_count)
+            .set("segwit_tx_percent", metrics.segwit_tx_percent.to_string())
+            .set("taproot_tx_percent", metrics.taproot_tx_percent.to_string())
+            .set("avg_difficulty", metrics.avg_difficulty.to_string())
+            .set("active_addresses", metrics.active_addresses);
+    }
+}
+
+fn epoch_changes(tables: &mut Tables, records: &EpochMetricsRecords) {
+    for record in &records.records {
+        let Some(metrics) = &record.metrics else {
+            continue;
+        };
+        tables
+            .create_row(&record.table, metrics.epoch.to_string())
+            .set("epoch", metrics.epoch)
+            .set("start_height", metrics.start_height)
+            .set("end_height", metrics.end_height)
+            .set("start_time", Timestamp(metrics.start_time as u64))
+            .set("end_time", Timestamp(metrics.end_time as u64))
+            .set("actual_timespan", metrics.actual_timespan)
+            .set("target_timespan", metrics.target_timespan)
+            .set("bits", metrics.bits)
+            .set("expected_bits", metrics.expected_bits)
+            .set("next_bits", metrics.next_bits)
+            .set("bits_match", metrics.bits_match as u8)
+            .set("adjustment_percent", metrics.adjustment_percent.to_string())
+            .set("hashrate", metrics.hashrate.to_string());
+    }
+}
+
+fn deployment_changes(tables: &mut Tables, records: &DeploymentPeriodRecords) {
+    for record in &records.records {
+        let Some(period) = &record.period else {
+            continue;
+        };
+        tables
+            .create_row(
+                &record.table,
+                [
+                    ("deployment", period.deployment.clone()),
+                    ("period", period.period.to_string()),
+                ],
+            )
+            .set("deployment", &period.deployment)
+            .set("bit", period.bit)
+            .set("period", period.period)
+            .set("start_height", period.start_height)
+            .set("end_height", period.end_height)
+            .set("signal_count", period.signal_count)
+            .set("threshold", period.threshold)
+            .set("period_length", period.period_length)
+            .set("state", &period.state)
+            .set("next_state", &period.next_state)
+            .set("transition", period.transition as u8);
+    }
+}
+
+fn inscription_changes(tables: &mut Tables, records: &InscriptionRecords) {
+    for record in &records.records {
+        let Some(inscription) = &record.inscription else {
+            continue;
+        };
+        tables
+            .create_row(&record.table, inscription.inscription_id.clone())
+            .set("inscription_id", &inscription.inscription_id)
+            .set("tx_id", &inscription.tx_id)
+            .set("input_index", inscription.input_index)
+            .set("envelope_index", inscription.envelope_index)
+            .set("block_height", inscription.block_height)
+            .set("block_time", Timestamp(inscription.block_time))
+            .set("content_type", &inscription.content_type)
+            .set("content_encoding", &inscription.content_encoding)
+            .set("has_body", inscription.has_body as u8)
+            .set("body_length", inscription.body_length)
+            .set("parents", inscription.parents.join(","))
+            .set("metadata", &inscription.metadata)
+            .set("metaprotocol", &inscription.metaprotocol)
+            .set("has_pointer", inscription.has_pointer as u8)
+            .set("pointer", inscription.pointer)
+            .set("delegate", &inscription.delegate)
+            .set("duplicate_field", inscription.duplicate_field as u8)
+            .set("incomplete_field", inscription.incomplete_field as u8)
+            .set("unrecognized_even_field", inscription.unrecognized_even_field as u8);
+    }
+}
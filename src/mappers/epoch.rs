@@ -0,0 +1,102 @@
+use crate::pb::bitcoin::analytics::v1::{EpochBoundary, EpochMetrics};
+use crate::utils::compact;
+use primitive_types::U256;
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+/// Number of blocks between two difficulty retargets
+pub const BLOCKS_PER_EPOCH: u64 = 2016;
+
+/// Expected duration of an epoch, two weeks in seconds
+pub const TARGET_TIMESPAN: i64 = 14 * 24 * 60 * 60;
+
+/// Compact form of the mainnet proof-of-work limit, the easiest allowed target
+pub const POW_LIMIT_BITS: u32 = 0x1d00ffff;
+
+// Fields of the epoch boundaries kept in `store_epoch_boundaries`
+pub const FIRST_BLOCK: &str = "first";
+pub const LAST_BLOCK: &str = "last";
+
+/// Epoch a block height belongs to
+pub fn epoch_of(height: u64) -> u64 {
+    height / BLOCKS_PER_EPOCH
+}
+
+/// Key of an epoch boundary
+pub fn epoch_key(epoch: u64, field: &str) -> String {
+    format!("{}:{}", epoch, field)
+}
+
+/// Prefix shared by both boundaries of `epoch`
+pub fn epoch_prefix(epoch: u64) -> String {
+    format!("{}:", epoch)
+}
+
+/// Field under which a block is kept when it starts or ends its epoch
+pub fn boundary_field(height: u64) -> Option<&'static str> {
+    match height % BLOCKS_PER_EPOCH {
+        0 => Some(FIRST_BLOCK),
+        position if position == BLOCKS_PER_EPOCH - 1 => Some(LAST_BLOCK),
+        _ => None,
+    }
+}
+
+/// Extract the header fields of an epoch boundary block
+pub fn extract_epoch_boundary(block: &Block) -> Result<EpochBoundary, Error> {
+    Ok(EpochBoundary {
+        number: block.height as u64,
+        time: block.time,
+        bits: compact::parse_bits(&block.bits)?,
+    })
+}
+
+/// Recompute the compact target of the next epoch, as `CalculateNextWorkRequired`
+///
+/// `timespan` is measured from the first to the last block of the epoch, so it covers
+/// 2015 intervals rather than 2016; consensus keeps that off-by-one and so do we. The
+/// timespan is clamped to a factor of 4 around two weeks and the target to the pow limit.
+pub fn expected_bits(bits: u32, timespan: i64) -> Result<u32, Error> {
+    let timespan = timespan.clamp(TARGET_TIMESPAN / 4, TARGET_TIMESPAN * 4);
+    let target = compact::decode_compact(bits)?
+        .checked_mul(U256::from(timespan as u64))
+        .ok_or_else(|| Error::msg(format!("Retarget of compact target {:08x} overflows 256 bits", bits)))?
+        / U256::from(TARGET_TIMESPAN as u64);
+    let pow_limit = compact::decode_compact(POW_LIMIT_BITS)?;
+    Ok(compact::encode_compact(target.min(pow_limit)))
+}
+
+/// Build the metrics of a completed epoch from its boundaries and the first block of the next one
+pub fn epoch_metrics(first: &EpochBoundary, last: &EpochBoundary, next: &Block) -> Result<EpochMetrics, Error> {
+    let actual_timespan = last.time - first.time;
+    let expected_bits = expected_bits(last.bits, actual_timespan)?;
+    let next_bits = compact::parse_bits(&next.bits)?;
+
+    let difficulty = compact::difficulty(last.bits);
+    let adjustment_percent = if difficulty > 0.0 {
+        (compact::difficulty(next_bits) / difficulty - 1.0) * 100.0
+    } else {
+        0.0
+    };
+    // A block at difficulty 1 takes 2^32 hashes on average
+    let hashrate = if actual_timespan > 0 {
+        difficulty * 4_294_967_296.0 * (BLOCKS_PER_EPOCH - 1) as f64 / actual_timespan as f64
+    } else {
+        0.0
+    };
+
+    Ok(EpochMetrics {
+        epoch: epoch_of(first.number),
+        start_height: first.number,
+        end_height: last.number,
+        start_time: first.time,
+        end_time: last.time,
+        actual_timespan,
+        target_timespan: TARGET_TIMESPAN,
+        bits: last.bits,
+        expected_bits,
+        next_bits,
+        bits_match: next_bits == expected_bits,
+        adjustment_percent,
+        hashrate,
+    })
+}
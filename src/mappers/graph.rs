@@ -0,0 +1,119 @@
+use crate::mappers::utxo::UNRESOLVED_SCRIPT_TYPE;
+use crate::pb::bitcoin::analytics::v1::{AddressActivityRecords, BlockMetricsRecord, TransactionMetricsRecords};
+use crate::pb::bitcoin::utxo::v1::UTXORecords;
+use crate::utils::pools::PoolMatchMethod;
+use substreams_entity_change::pb::entity::EntityChanges;
+use substreams_entity_change::tables::Tables;
+
+/// Id of a Transaction entity: two early coinbases share their txid (BIP30)
+fn transaction_id(tx_id: &str, block_height: u64) -> String {
+    format!("{}:{}", tx_id, block_height)
+}
+
+/// Convert the records of a block to the entity changes of `schema.graphql`
+///
+/// Entities are keyed by block height, `txid:height`, `txid:vout`, address and pool name.
+/// Updates are upserts, so an output spent in a later block is completed in place, and
+/// changes to the same entity within a block are merged.
+pub fn entity_changes(
+    block: &BlockMetricsRecord,
+    transactions: &TransactionMetricsRecords,
+    utxos: &UTXORecords,
+    activities: &AddressActivityRecords,
+) -> EntityChanges {
+    let mut tables = Tables::new();
+    let Some(block) = &block.block else {
+        return tables.to_entity_changes();
+    };
+    let block_id = block.number.to_string();
+
+    let has_pool = block.miner_match_method != PoolMatchMethod::Unknown.as_str();
+    if has_pool {
+        tables
+            .update_row("Pool", &block.miner)
+            .set("name", &block.miner)
+            .set("lastBlock", &block_id);
+    }
+
+    let row = tables.create_row("Block", &block_id);
+    row.set("number", block.number)
+        .set("hash", &block.hash)
+        .set("timestamp", block.timestamp)
+        .set("size", block.size as i32)
+        .set("weight", block.weight as i32)
+        .set("txCount", block.tx_count as i32)
+        .set_bigdecimal("difficulty", &block.difficulty.to_string())
+        .set("work", &block.work)
+        .set("chainwork", &block.chainwork)
+        .set("poolMatchMethod", &block.miner_match_method)
+        .set("blockInterval", block.block_interval)
+        .set("totalFees", block.total_fees)
+        .set("feesResolved", block.fees_resolved)
+        .set("blockReward", block.block_reward);
+    if has_pool {
+        row.set("pool", &block.miner);
+    }
+
+    for tx in transactions.records.iter().filter_map(|record| record.transaction.as_ref()) {
+        tables
+            .create_row("Transaction", transaction_id(&tx.hash, tx.block_number))
+            .set("hash", &tx.hash)
+            .set("wtxid", &tx.wtxid)
+            .set("block", &block_id)
+            .set("timestamp", tx.block_timestamp)
+            .set("inputCount", tx.input_count as i32)
+            .set("outputCount", tx.output_count as i32)
+            .set("inputValue", tx.input_value)
+            .set("outputValue", tx.output_value)
+            .set("fee", tx.fee)
+            .set_bigdecimal("feeRate", &tx.fee_rate.to_string())
+            .set("size", tx.size as i32)
+            .set("weight", tx.weight as i32)
+            .set("isCoinbase", tx.is_coinbase)
+            .set("hasWitness", tx.has_witness)
+            .set("txType", &tx.tx_type)
+            .set("inputsResolved", tx.inputs_resolved);
+    }
+
+    // Addresses are upserted before the outputs referencing them. Only the pool of the block
+    // has a Pool entity, so payout addresses of other pools aren't linked.
+    for activity in activities.records.iter().filter_map(|record| record.activity.as_ref()) {
+        let row = tables.update_row("Address", &activity.address);
+        row.set("lastActivityBlock", &block_id);
+        if has_pool && activity.address_category == "MINING_POOL" && activity.address_tag == block.miner {
+            row.set("pool", &activity.address_tag);
+        }
+    }
+
+    for utxo in utxos.records.iter().filter_map(|record| record.utxo.as_ref()) {
+        // An output missing from the store has no known creation transaction or block to link to
+        if utxo.is_spent && utxo.script_type == UNRESOLVED_SCRIPT_TYPE {
+            continue;
+        }
+        let id = format!("{}:{}", utxo.tx_id, utxo.vout_index);
+        let row = if utxo.is_spent {
+            tables.update_row("UTXO", &id)
+        } else {
+            tables.create_row("UTXO", &id)
+        };
+        row.set("transaction", transaction_id(&utxo.tx_id, utxo.block_height))
+            .set("voutIndex", utxo.vout_index as i32)
+            .set("value", utxo.value)
+            .set("scriptType", &utxo.script_type)
+            .set("block", utxo.block_height.to_string())
+            .set("isSpent", utxo.is_spent);
+        if !utxo.address.is_empty() {
+            row.set("address", &utxo.address);
+        }
+        if utxo.is_spent {
+            row.set(
+                "spentInTransaction",
+                transaction_id(&utxo.spent_in_tx_id, utxo.spent_at_block_height),
+            )
+                .set("spentInInputIndex", utxo.spent_in_input_index as i32)
+                .set("spentAtBlock", utxo.spent_at_block_height.to_string());
+        }
+    }
+
+    tables.to_entity_changes()
+}
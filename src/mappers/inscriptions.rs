@@ -0,0 +1,176 @@
+use crate::mappers::taproot::{self, TaprootSpend};
+use crate::pb::bitcoin::analytics::v1::{Inscription, InscriptionRecord};
+use crate::utils::ids::Txid;
+use crate::utils::script::{self, Instruction, OP_1NEGATE, OP_ENDIF, OP_IF};
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+/// Push following `OP_FALSE OP_IF` that marks an ordinals envelope
+const PROTOCOL_ID: &[u8] = b"ord";
+
+/// Field tags of an inscription; odd tags may be ignored, unknown even tags make it unbound
+const CONTENT_TYPE_TAG: u8 = 1;
+const POINTER_TAG: u8 = 2;
+const PARENT_TAG: u8 = 3;
+const METADATA_TAG: u8 = 5;
+const METAPROTOCOL_TAG: u8 = 7;
+const CONTENT_ENCODING_TAG: u8 = 9;
+const DELEGATE_TAG: u8 = 11;
+
+/// Size of an inscription id: a txid, then its envelope index as up to 4 little-endian bytes
+const INSCRIPTION_ID_MIN_SIZE: usize = 32;
+const INSCRIPTION_ID_MAX_SIZE: usize = 36;
+
+/// Payloads of the `OP_FALSE OP_IF "ord" ... OP_ENDIF` envelopes of a tapscript
+///
+/// Pushes after the protocol id are collected up to `OP_ENDIF`, `OP_1NEGATE` and `OP_1` to
+/// `OP_16` standing for one-byte pushes. An envelope holding any other opcode or missing its
+/// `OP_ENDIF` is skipped, and a script with a truncated push has no envelope, as in `ord`.
+pub fn parse_envelopes(tapscript: &[u8]) -> Vec<Vec<Vec<u8>>> {
+    let Ok(instructions) = script::instructions(tapscript).collect::<Result<Vec<_>, _>>() else {
+        return Vec::new();
+    };
+
+    let mut envelopes = Vec::new();
+    for start in 0..instructions.len() {
+        let header = &instructions[start..instructions.len().min(start + 3)];
+        if header != [Instruction::PushBytes(&[]), Instruction::Op(OP_IF), Instruction::PushBytes(PROTOCOL_ID)] {
+            continue;
+        }
+        let mut payload = Vec::new();
+        for instruction in &instructions[start + 3..] {
+            match *instruction {
+                Instruction::Op(OP_ENDIF) => {
+                    envelopes.push(payload);
+                    break;
+                }
+                Instruction::PushBytes(bytes) => payload.push(bytes.to_vec()),
+                Instruction::Op(OP_1NEGATE) => payload.push(vec![0x81]),
+                Instruction::Op(opcode) => match script::small_int_value(opcode).filter(|value| *value > 0) {
+                    Some(value) => payload.push(vec![value]),
+                    None => break,
+                },
+            }
+        }
+    }
+    envelopes
+}
+
+/// Decode the fields and body of an envelope payload into an inscription
+///
+/// The body starts after the first empty push at a tag position; the pushes before it are
+/// tag and value pairs.
+pub fn decode_payload(payload: &[Vec<u8>]) -> Inscription {
+    let body_start = payload
+        .iter()
+        .enumerate()
+        .position(|(index, push)| index % 2 == 0 && push.is_empty());
+    let fields = &payload[..body_start.unwrap_or(payload.len())];
+
+    let mut inscription = Inscription {
+        has_body: body_start.is_some(),
+        body_length: body_start.map_or(0, |start| payload[start + 1..].iter().map(Vec::len).sum::<usize>()) as u64,
+        incomplete_field: fields.len() % 2 == 1,
+        ..Default::default()
+    };
+
+    let mut metadata = Vec::new();
+    let mut seen: Vec<&[u8]> = Vec::new();
+    for field in fields.chunks_exact(2) {
+        let (tag, value) = (field[0].as_slice(), field[1].as_slice());
+        let repeatable = matches!(tag, [PARENT_TAG] | [METADATA_TAG]);
+        if seen.contains(&tag) && !repeatable {
+            inscription.duplicate_field = true;
+            continue;
+        }
+        seen.push(tag);
+
+        match tag {
+            [CONTENT_TYPE_TAG] => inscription.content_type = String::from_utf8_lossy(value).into_owned(),
+            [CONTENT_ENCODING_TAG] => inscription.content_encoding = String::from_utf8_lossy(value).into_owned(),
+            [METAPROTOCOL_TAG] => inscription.metaprotocol = String::from_utf8_lossy(value).into_owned(),
+            [METADATA_TAG] => metadata.extend_from_slice(value),
+            [PARENT_TAG] => inscription.parents.extend(inscription_id(value)),
+            [DELEGATE_TAG] => inscription.delegate = inscription_id(value).unwrap_or_default(),
+            [POINTER_TAG] => {
+                if let Some(pointer) = pointer(value) {
+                    inscription.has_pointer = true;
+                    inscription.pointer = pointer;
+                }
+            }
+            _ => {
+                if tag.first().is_some_and(|lsb| lsb % 2 == 0) {
+                    inscription.unrecognized_even_field = true;
+                }
+            }
+        }
+    }
+    inscription.metadata = hex::encode(metadata);
+
+    inscription
+}
+
+/// Decode an inscription id value into `<txid>i<index>`, `None` unless the index is minimal
+pub fn inscription_id(value: &[u8]) -> Option<String> {
+    if !(INSCRIPTION_ID_MIN_SIZE..=INSCRIPTION_ID_MAX_SIZE).contains(&value.len()) {
+        return None;
+    }
+    let (txid, index) = value.split_at(INSCRIPTION_ID_MIN_SIZE);
+    if index.last() == Some(&0) {
+        return None;
+    }
+    let index = index.iter().rev().fold(0u32, |value, byte| value << 8 | *byte as u32);
+    Some(format!("{}i{}", Txid::from_byte_array(txid.try_into().ok()?), index))
+}
+
+/// Decode a little-endian pointer, `None` when it doesn't fit in 64 bits
+fn pointer(value: &[u8]) -> Option<u64> {
+    if value.iter().skip(8).any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(value.iter().take(8).rev().fold(0u64, |pointer, byte| pointer << 8 | *byte as u64))
+}
+
+/// Extract the inscriptions revealed by the script-path spends of a block
+///
+/// Envelopes are numbered across the inputs of their transaction, as in inscription ids.
+pub fn extract_inscriptions(block: &Block) -> Result<Vec<InscriptionRecord>, Error> {
+    let mut records = Vec::new();
+
+    for tx in &block.tx {
+        let tx_id = Txid::from_hex(&tx.txid)?.to_string();
+        let mut envelope_index = 0u32;
+
+        for (input_index, vin) in tx.vin.iter().enumerate() {
+            let witness = vin
+                .txinwitness
+                .iter()
+                .map(hex::decode)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| Error::msg(format!("Invalid witness in input {}:{}", vin.txid, vin.vout)))?;
+            let Some(TaprootSpend::ScriptPath { tapscript, .. }) = taproot::parse_witness(&witness).map(|parsed| parsed.spend)
+            else {
+                continue;
+            };
+
+            for payload in parse_envelopes(tapscript) {
+                let inscription = Inscription {
+                    block_height: block.height as u64,
+                    block_time: block.time as u64,
+                    tx_id: tx_id.clone(),
+                    input_index: input_index as u32,
+                    envelope_index,
+                    inscription_id: format!("{}i{}", tx_id, envelope_index),
+                    ..decode_payload(&payload)
+                };
+                records.push(InscriptionRecord {
+                    table: "bitcoin_inscriptions".to_string(),
+                    inscription: Some(inscription),
+                });
+                envelope_index += 1;
+            }
+        }
+    }
+
+    Ok(records)
+}
@@ -1,5 +1,19 @@
+pub mod address;
 pub mod block;
+pub mod coinbase;
+pub mod daily;
+pub mod db;
+pub mod epoch;
+pub mod graph;
+pub mod inscriptions;
+#[cfg(feature = "sat-ranges")]
+pub mod sats;
+pub mod spend;
+pub mod taproot;
+pub mod transaction;
 pub mod utxo;
+pub mod validation;
+pub mod versionbits;
 
 #[cfg(test)]
 mod tests;
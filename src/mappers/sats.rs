@@ -0,0 +1,250 @@
+use crate::mappers::block::{block_subsidy, BLOCKS_PER_HALVING};
+use crate::mappers::epoch::BLOCKS_PER_EPOCH;
+use crate::mappers::utxo::{self, UtxoLookup};
+use crate::pb::bitcoin::analytics::v1::{OutputSatRanges, RareSat, SatRange, SatRangeRecord};
+use crate::utils;
+use crate::utils::amount::Amount;
+use crate::utils::ids::Txid;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+/// Params of `map_sat_ranges`, e.g. `{"start_height": 840000}`
+#[derive(Debug, Default, Deserialize)]
+struct SatRangeParams {
+    #[serde(default)]
+    start_height: u64,
+}
+
+/// First block whose sat ranges are tracked, read from the module params
+pub fn start_height(params: &str) -> Result<u64, Error> {
+    if params.trim().is_empty() {
+        return Ok(0);
+    }
+    let params: SatRangeParams =
+        serde_json::from_str(params).map_err(|e| Error::msg(format!("Invalid sat range params: {}", e)))?;
+    Ok(params.start_height)
+}
+
+/// Rarity of a sat in ordinal theory
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rarity {
+    Common,
+    /// First sat of a block
+    Uncommon,
+    /// First sat of a difficulty adjustment period
+    Rare,
+    /// First sat of a halving epoch
+    Epic,
+    /// First sat of a cycle, when a halving and a difficulty adjustment coincide every 6 halvings
+    Legendary,
+    /// First sat of the genesis block
+    Mythic,
+}
+
+impl Rarity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Rarity::Common => "common",
+            Rarity::Uncommon => "uncommon",
+            Rarity::Rare => "rare",
+            Rarity::Epic => "epic",
+            Rarity::Legendary => "legendary",
+            Rarity::Mythic => "mythic",
+        }
+    }
+}
+
+/// First sat of the block at `height`: the subsidies of all the blocks before it
+pub fn first_sat(height: u64) -> u64 {
+    let halvings = height / BLOCKS_PER_HALVING;
+    let previous_epochs: u64 = (0..halvings)
+        .map(|halving| block_subsidy(halving * BLOCKS_PER_HALVING) * BLOCKS_PER_HALVING)
+        .sum();
+    previous_epochs + (height % BLOCKS_PER_HALVING) * block_subsidy(height)
+}
+
+/// Height of the block whose subsidy mined `sat`, and the offset of the sat in that subsidy
+///
+/// Returns `None` past the last sat, once the subsidy has halved to zero.
+pub fn sat_position(sat: u64) -> Option<(u64, u64)> {
+    let mut epoch_start = 0u64;
+    let mut halving = 0u64;
+    loop {
+        let subsidy = block_subsidy(halving * BLOCKS_PER_HALVING);
+        if subsidy == 0 {
+            return None;
+        }
+        let epoch_sats = subsidy * BLOCKS_PER_HALVING;
+        if sat < epoch_start + epoch_sats {
+            let offset = sat - epoch_start;
+            return Some((halving * BLOCKS_PER_HALVING + offset / subsidy, offset % subsidy));
+        }
+        epoch_start += epoch_sats;
+        halving += 1;
+    }
+}
+
+/// Rarity of a sat, from the position of its block in the difficulty and halving schedules
+pub fn rarity(sat: u64) -> Rarity {
+    match sat_position(sat) {
+        Some((height, 0)) => {
+            let halving = height % BLOCKS_PER_HALVING == 0;
+            let adjustment = height % BLOCKS_PER_EPOCH == 0;
+            match (height, halving, adjustment) {
+                (0, _, _) => Rarity::Mythic,
+                (_, true, true) => Rarity::Legendary,
+                (_, true, false) => Rarity::Epic,
+                (_, false, true) => Rarity::Rare,
+                (_, false, false) => Rarity::Uncommon,
+            }
+        }
+        _ => Rarity::Common,
+    }
+}
+
+/// Sats carried from inputs to outputs, either a known range or a number of untracked sats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Span {
+    /// Sats `start..end`
+    Known { start: u64, end: u64 },
+    /// Sats of outputs created before tracking started
+    Unknown(u64),
+}
+
+impl Span {
+    fn size(&self) -> u64 {
+        match *self {
+            Span::Known { start, end } => end - start,
+            Span::Unknown(size) => size,
+        }
+    }
+
+    /// Split after `size` sats
+    fn split(self, size: u64) -> (Span, Span) {
+        match self {
+            Span::Known { start, end } => (
+                Span::Known { start, end: start + size },
+                Span::Known { start: start + size, end },
+            ),
+            Span::Unknown(total) => (Span::Unknown(size), Span::Unknown(total - size)),
+        }
+    }
+}
+
+/// Take the first `amount` sats of `spans`, first-in-first-out
+///
+/// Spans are never merged, so a known span always lies within the subsidy of a single block.
+/// Sats missing from `spans`, e.g. when an input value is unresolved, are returned as unknown.
+fn take(spans: &mut VecDeque<Span>, amount: u64) -> Vec<Span> {
+    let mut taken = Vec::new();
+    let mut remaining = amount;
+    while remaining > 0 {
+        let Some(span) = spans.pop_front() else {
+            taken.push(Span::Unknown(remaining));
+            break;
+        };
+        if span.size() > remaining {
+            let (head, tail) = span.split(remaining);
+            spans.push_front(tail);
+            taken.push(head);
+            break;
+        }
+        remaining -= span.size();
+        if span.size() > 0 {
+            taken.push(span);
+        }
+    }
+    taken
+}
+
+/// Record of an output holding at least one known sat range
+fn output_record(block: &Block, tx_id: &str, vout_index: u32, spans: &[Span]) -> Option<SatRangeRecord> {
+    let mut output = OutputSatRanges {
+        block_height: block.height as u64,
+        tx_id: tx_id.to_string(),
+        vout_index,
+        ..Default::default()
+    };
+    let mut offset = 0;
+    for span in spans {
+        match *span {
+            Span::Known { start, end } => {
+                output.ranges.push(SatRange { start, end });
+                // Only the first sat of a block is rare, and it can only start a span
+                let rarity = rarity(start);
+                if rarity != Rarity::Common {
+                    output.rare_sats.push(RareSat {
+                        sat: start,
+                        rarity: rarity.as_str().to_string(),
+                        offset,
+                    });
+                }
+            }
+            Span::Unknown(size) => output.unknown_sats += size,
+        }
+        output.value += span.size();
+        offset += span.size();
+    }
+
+    (!output.ranges.is_empty()).then_some(SatRangeRecord { output: Some(output) })
+}
+
+/// Assign the sat ranges of a block first-in-first-out, returning the outputs holding known sats
+///
+/// The coinbase outputs receive the block subsidy range, then the fee sats left over by each
+/// transaction in block order; sats the coinbase doesn't claim are lost. `carried` holds the
+/// spans of unspent outputs by `txid:vout` key: spent outputs are removed from it and new
+/// outputs added. Inputs missing from it contribute the value resolved from `utxo_store` as
+/// unknown sats.
+pub fn assign_sat_ranges<S: UtxoLookup>(
+    block: &Block,
+    utxo_store: &S,
+    carried: &mut HashMap<String, Vec<Span>>,
+) -> Result<Vec<SatRangeRecord>, Error> {
+    let mut records = Vec::new();
+    let mut fee_spans = VecDeque::new();
+
+    for (tx_idx, tx) in block.tx.iter().enumerate() {
+        if utils::bitcoin_utils::is_coinbase_transaction(tx) {
+            continue;
+        }
+        let tx_id = Txid::from_hex(&tx.txid)?.to_string();
+        let prevouts = utxo::resolve_prevouts(tx, tx_idx, utxo_store);
+
+        let mut input_spans = VecDeque::new();
+        for (vin, prevout) in tx.vin.iter().zip(&prevouts) {
+            let key = utxo::utxo_key(&Txid::from_hex(&vin.txid)?.to_string(), vin.vout);
+            match carried.remove(&key) {
+                Some(spans) => input_spans.extend(spans),
+                None => input_spans.push_back(Span::Unknown(prevout.as_ref().map_or(0, |prevout| prevout.value))),
+            }
+        }
+
+        for (vout_index, vout) in tx.vout.iter().enumerate() {
+            let spans = take(&mut input_spans, Amount::from_btc(vout.value)?.to_sat());
+            records.extend(output_record(block, &tx_id, vout_index as u32, &spans));
+            carried.insert(utxo::utxo_key(&tx_id, vout_index as u32), spans);
+        }
+        fee_spans.extend(input_spans);
+    }
+
+    if let Some(coinbase) = block.tx.iter().find(|tx| utils::bitcoin_utils::is_coinbase_transaction(tx)) {
+        let tx_id = Txid::from_hex(&coinbase.txid)?.to_string();
+        let height = block.height as u64;
+        let subsidy_start = first_sat(height);
+        fee_spans.push_front(Span::Known {
+            start: subsidy_start,
+            end: subsidy_start + block_subsidy(height),
+        });
+
+        for (vout_index, vout) in coinbase.vout.iter().enumerate() {
+            let spans = take(&mut fee_spans, Amount::from_btc(vout.value)?.to_sat());
+            records.extend(output_record(block, &tx_id, vout_index as u32, &spans));
+            carried.insert(utxo::utxo_key(&tx_id, vout_index as u32), spans);
+        }
+    }
+
+    Ok(records)
+}
@@ -0,0 +1,150 @@
+use crate::mappers::taproot::{self, TaprootSpend, TaprootWitness};
+use crate::utils::script::{self, Instruction};
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Vin;
+
+/// How an input spent its output, inferred from its scriptSig and witness
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpendType {
+    /// scriptSig <sig>
+    P2pk,
+    /// scriptSig <sig> <pubkey>
+    P2pkh,
+    /// scriptSig ending with a redeem script, without witness
+    P2sh,
+    /// scriptSig <0 <20 bytes>>, witness <sig> <pubkey>
+    P2shP2wpkh,
+    /// scriptSig <0 <32 bytes>>, witness ending with the witness script
+    P2shP2wsh,
+    /// Empty scriptSig, witness <sig> <pubkey>
+    P2wpkh,
+    /// Empty scriptSig, witness ending with the witness script
+    P2wsh,
+    /// Empty scriptSig, witness <schnorr sig> [annex]
+    TaprootKeyPath,
+    /// Empty scriptSig, witness ending with <tapscript> <control block> [annex]
+    TaprootScriptPath,
+    /// scriptSig OP_0 <sig>...
+    Multisig,
+    Unknown,
+}
+
+impl SpendType {
+    /// Name of the type, as stored in `spend_type` columns
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpendType::P2pk => "P2PK",
+            SpendType::P2pkh => "P2PKH",
+            SpendType::P2sh => "P2SH",
+            SpendType::P2shP2wpkh => "P2SH_P2WPKH",
+            SpendType::P2shP2wsh => "P2SH_P2WSH",
+            SpendType::P2wpkh => "P2WPKH",
+            SpendType::P2wsh => "P2WSH",
+            SpendType::TaprootKeyPath => "P2TR_KEY_PATH",
+            SpendType::TaprootScriptPath => "P2TR_SCRIPT_PATH",
+            SpendType::Multisig => "MULTISIG",
+            SpendType::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// Whether the spend uses a witness, natively or nested in P2SH
+    pub fn is_segwit(&self) -> bool {
+        matches!(
+            self,
+            SpendType::P2shP2wpkh
+                | SpendType::P2shP2wsh
+                | SpendType::P2wpkh
+                | SpendType::P2wsh
+                | SpendType::TaprootKeyPath
+                | SpendType::TaprootScriptPath
+        )
+    }
+
+    /// Whether the spend is a taproot key-path or script-path spend
+    pub fn is_taproot(&self) -> bool {
+        matches!(self, SpendType::TaprootKeyPath | SpendType::TaprootScriptPath)
+    }
+}
+
+/// Classify how a non-coinbase input spent its output
+pub fn classify_input(vin: &Vin) -> Result<SpendType, Error> {
+    let script_sig = match &vin.script_sig {
+        Some(script_sig) => hex::decode(&script_sig.hex)
+            .map_err(|_| Error::msg(format!("Invalid scriptSig in input {}:{}", vin.txid, vin.vout)))?,
+        None => Vec::new(),
+    };
+    let witness = vin
+        .txinwitness
+        .iter()
+        .map(hex::decode)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Error::msg(format!("Invalid witness in input {}:{}", vin.txid, vin.vout)))?;
+
+    Ok(classify_spend(&script_sig, &witness))
+}
+
+/// Classify a spend from its scriptSig and witness stack
+///
+/// Without the spent output this is an inference: it matches the shapes standard wallets produce,
+/// and anything else is `Unknown`.
+pub fn classify_spend(script_sig: &[u8], witness: &[Vec<u8>]) -> SpendType {
+    // A scriptSig that is not push-only cannot be classified
+    let Some(pushes) = script::instructions(script_sig)
+        .map(|instruction| match instruction {
+            Ok(Instruction::PushBytes(bytes)) => Some(bytes),
+            _ => None,
+        })
+        .collect::<Option<Vec<&[u8]>>>()
+    else {
+        return SpendType::Unknown;
+    };
+
+    if witness.is_empty() {
+        return match pushes.as_slice() {
+            [sig] if is_ecdsa_signature(sig) => SpendType::P2pk,
+            [sig, pubkey] if is_ecdsa_signature(sig) && script::is_pubkey(pubkey) => SpendType::P2pkh,
+            [dummy, sigs @ ..] if dummy.is_empty() && !sigs.is_empty() && sigs.iter().all(|sig| is_ecdsa_signature(sig)) => {
+                SpendType::Multisig
+            }
+            [.., redeem_script] if !redeem_script.is_empty() => SpendType::P2sh,
+            _ => SpendType::Unknown,
+        };
+    }
+
+    match pushes.as_slice() {
+        [] => native_witness_spend(witness),
+        [redeem_script] => match script::witness_program(redeem_script) {
+            Some((0, program)) if program.len() == 20 => SpendType::P2shP2wpkh,
+            Some((0, program)) if program.len() == 32 => SpendType::P2shP2wsh,
+            _ => SpendType::Unknown,
+        },
+        _ => SpendType::Unknown,
+    }
+}
+
+/// Classify a spend with an empty scriptSig from its witness stack
+fn native_witness_spend(witness: &[Vec<u8>]) -> SpendType {
+    if let [sig, pubkey] = witness {
+        if is_ecdsa_signature(sig) && pubkey.len() == 33 && script::is_pubkey(pubkey) {
+            return SpendType::P2wpkh;
+        }
+    }
+
+    // Only tapscript leaves are told apart from P2WSH witness scripts, other leaf versions are unused
+    match taproot::parse_witness(witness) {
+        Some(TaprootWitness {
+            spend: TaprootSpend::KeyPath { signature },
+            ..
+        }) if signature.len() == 64 || signature.len() == 65 => SpendType::TaprootKeyPath,
+        Some(TaprootWitness {
+            spend: TaprootSpend::ScriptPath { control_block, .. },
+            ..
+        }) if control_block.leaf_version == taproot::TAPSCRIPT_LEAF_VERSION => SpendType::TaprootScriptPath,
+        _ => SpendType::P2wsh,
+    }
+}
+
+/// Whether bytes look like a DER-encoded ECDSA signature followed by its sighash byte
+fn is_ecdsa_signature(bytes: &[u8]) -> bool {
+    (9..=73).contains(&bytes.len()) && bytes[0] == 0x30 && bytes[1] as usize == bytes.len() - 3
+}
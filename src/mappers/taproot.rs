@@ -0,0 +1,146 @@
+use crate::mappers::spend;
+use crate::mappers::utxo::{self, UtxoLookup};
+use crate::pb::bitcoin::analytics::v1::{TaprootInput, TaprootMetrics};
+use crate::utils;
+use crate::utils::ids::Txid;
+use crate::utils::script::{self, ScriptType};
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+/// First byte of the annex, the optional last witness item (BIP341)
+pub const ANNEX_TAG: u8 = 0x50;
+
+/// Leaf version of tapscript (BIP342)
+pub const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+/// A control block holds the leaf version and output key parity byte, the x-only internal key,
+/// then up to 128 nodes of the merkle path to the script leaf
+const CONTROL_BLOCK_BASE_SIZE: usize = 33;
+const CONTROL_BLOCK_NODE_SIZE: usize = 32;
+const CONTROL_BLOCK_MAX_NODES: usize = 128;
+
+/// Decoded control block of a script-path spend
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ControlBlock<'a> {
+    pub leaf_version: u8,
+    /// Whether the y coordinate of the output key is odd
+    pub output_key_parity: bool,
+    pub internal_key: &'a [u8],
+    pub merkle_path: Vec<&'a [u8]>,
+}
+
+/// How a taproot output was spent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TaprootSpend<'a> {
+    KeyPath { signature: &'a [u8] },
+    ScriptPath { tapscript: &'a [u8], control_block: ControlBlock<'a> },
+}
+
+/// Witness of an input spending a taproot output
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaprootWitness<'a> {
+    pub spend: TaprootSpend<'a>,
+    pub annex: Option<&'a [u8]>,
+}
+
+/// Decode a control block, `None` when its size is not 33 + 32m bytes with m <= 128
+pub fn parse_control_block(bytes: &[u8]) -> Option<ControlBlock<'_>> {
+    let path = bytes.get(CONTROL_BLOCK_BASE_SIZE..)?;
+    if !path.len().is_multiple_of(CONTROL_BLOCK_NODE_SIZE) || path.len() / CONTROL_BLOCK_NODE_SIZE > CONTROL_BLOCK_MAX_NODES {
+        return None;
+    }
+    Some(ControlBlock {
+        leaf_version: bytes[0] & 0xfe,
+        output_key_parity: bytes[0] & 0x01 == 1,
+        internal_key: &bytes[1..CONTROL_BLOCK_BASE_SIZE],
+        merkle_path: path.chunks(CONTROL_BLOCK_NODE_SIZE).collect(),
+    })
+}
+
+/// Split a taproot witness stack into its annex and its key-path or script-path spend
+pub fn parse_witness(witness: &[Vec<u8>]) -> Option<TaprootWitness<'_>> {
+    let (stack, annex) = match witness.split_last()? {
+        (last, rest) if !rest.is_empty() && last.first() == Some(&ANNEX_TAG) => (rest, Some(last.as_slice())),
+        _ => (witness, None),
+    };
+    let spend = match stack {
+        [signature] => TaprootSpend::KeyPath { signature },
+        [.., tapscript, control_block] => TaprootSpend::ScriptPath {
+            tapscript,
+            control_block: parse_control_block(control_block)?,
+        },
+        [] => return None,
+    };
+    Some(TaprootWitness { spend, annex })
+}
+
+/// Count the taproot outputs and spends of a block, with the details of each spend
+///
+/// Inputs are taproot spends when the output they spend is P2TR. Outputs missing from the store
+/// fall back to the spend type inferred from the witness.
+pub fn extract_taproot_metrics<S: UtxoLookup>(block: &Block, utxo_store: &S) -> Result<TaprootMetrics, Error> {
+    let mut metrics = TaprootMetrics {
+        block_height: block.height as u64,
+        block_time: block.time as u64,
+        ..Default::default()
+    };
+
+    for (tx_idx, tx) in block.tx.iter().enumerate() {
+        metrics.p2tr_outputs += tx
+            .vout
+            .iter()
+            .filter_map(|vout| vout.script_pub_key.as_ref())
+            .filter(|script| script::classify(&hex::decode(&script.hex).unwrap_or_default()) == ScriptType::P2tr)
+            .count() as u32;
+
+        if utils::bitcoin_utils::is_coinbase_transaction(tx) {
+            continue;
+        }
+        let prevouts = utxo::resolve_prevouts(tx, tx_idx, utxo_store);
+        for (input_index, (vin, prevout)) in tx.vin.iter().zip(&prevouts).enumerate() {
+            let spends_taproot = match prevout {
+                Some(prevout) => prevout.script_type == ScriptType::P2tr.name(),
+                None => spend::classify_input(vin)?.is_taproot(),
+            };
+            if !spends_taproot {
+                continue;
+            }
+
+            let witness = vin
+                .txinwitness
+                .iter()
+                .map(hex::decode)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| Error::msg(format!("Invalid witness in input {}:{}", vin.txid, vin.vout)))?;
+            // Spends before activation, when P2TR outputs were anyone-can-spend, may not parse
+            let Some(parsed) = parse_witness(&witness) else {
+                continue;
+            };
+
+            let mut input = TaprootInput {
+                tx_id: Txid::from_hex(&tx.txid)?.to_string(),
+                input_index: input_index as u32,
+                has_annex: parsed.annex.is_some(),
+                annex: parsed.annex.map(hex::encode).unwrap_or_default(),
+                ..Default::default()
+            };
+            match parsed.spend {
+                TaprootSpend::KeyPath { .. } => metrics.key_path_spends += 1,
+                TaprootSpend::ScriptPath { tapscript, control_block } => {
+                    metrics.script_path_spends += 1;
+                    input.script_path = true;
+                    input.leaf_version = control_block.leaf_version as u32;
+                    input.merkle_depth = control_block.merkle_path.len() as u32;
+                    input.internal_key = hex::encode(control_block.internal_key);
+                    input.tapscript = hex::encode(tapscript);
+                }
+            }
+            if input.has_annex {
+                metrics.annex_spends += 1;
+            }
+            metrics.inputs.push(input);
+        }
+    }
+
+    Ok(metrics)
+}
@@ -0,0 +1,94 @@
+use super::fixtures::{GENESIS_COINBASE_TXID, PREV_TXID, SEGWIT_TXID};
+use crate::mappers::address::extract_address_activity;
+use crate::mappers::utxo::utxo_key;
+use crate::pb::bitcoin::utxo::v1::UTXO;
+use crate::utils::pools::PoolDatabase;
+use std::collections::HashMap;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, ScriptPubKey, Transaction, Vin, Vout};
+
+fn vout(value: f64, script_hex: &str) -> Vout {
+    Vout {
+        value,
+        script_pub_key: Some(ScriptPubKey {
+            hex: script_hex.to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_extract_address_activity() {
+    let prev_tx_id = PREV_TXID.to_string();
+    let mut store = HashMap::new();
+    store.insert(
+        utxo_key(&prev_tx_id, 0),
+        UTXO {
+            tx_id: prev_tx_id,
+            value: 250_000,
+            script_type: "P2PKH".to_string(),
+            address: "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string(),
+            ..Default::default()
+        },
+    );
+
+    let block = Block {
+        height: 800_000,
+        time: 1_690_000_000,
+        tx: vec![
+            Transaction {
+                txid: GENESIS_COINBASE_TXID.to_string(),
+                vin: vec![Vin {
+                    coinbase: "0340350c".to_string(),
+                    ..Default::default()
+                }],
+                // AntPool payout address
+                vout: vec![vout(6.25, "76a91411dbe48cc6b617f9c6adaf4d9ed5f625b1c7cb5988ac")],
+                ..Default::default()
+            },
+            Transaction {
+                // Ids are normalized to lower case hex
+                txid: SEGWIT_TXID.to_uppercase(),
+                vin: vec![Vin {
+                    txid: PREV_TXID.to_string(),
+                    ..Default::default()
+                }],
+                vout: vec![
+                    vout(0.002, "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+                    vout(0.0, "6a0b68656c6c6f20776f726c64"),
+                ],
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let pools = PoolDatabase::load("").unwrap();
+    let records = extract_address_activity(&block, &store, &pools).unwrap();
+    let activities: Vec<_> = records.iter().map(|record| record.activity.as_ref().unwrap()).collect();
+
+    // Coinbase input and OP_RETURN output have no address
+    assert_eq!(activities.len(), 3);
+    assert!(records.iter().all(|record| record.table == "bitcoin_address_transactions"));
+
+    assert_eq!(activities[0].address, "12dRugNcdxK39288NjcDV4GX7rMsKCGn6B");
+    assert!(!activities[0].is_input);
+    assert_eq!(activities[0].value, 625_000_000);
+    assert_eq!(activities[0].address_tag, "AntPool");
+    assert_eq!(activities[0].address_category, "MINING_POOL");
+
+    assert_eq!(activities[1].address, "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa");
+    assert_eq!(activities[1].tx_hash, SEGWIT_TXID);
+    assert!(activities[1].is_input);
+    assert_eq!(activities[1].value, 250_000);
+    assert_eq!(activities[1].script_type, "P2PKH");
+    assert_eq!(activities[1].address_tag, "");
+
+    assert_eq!(activities[2].address, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+    assert!(!activities[2].is_input);
+    assert_eq!(activities[2].value, 200_000);
+    assert_eq!(activities[2].io_index, 0);
+    assert_eq!(activities[2].script_type, "P2WPKH");
+    assert_eq!(activities[2].block_number, 800_000);
+    assert_eq!(activities[2].block_timestamp, 1_690_000_000);
+}
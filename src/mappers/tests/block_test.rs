@@ -1,22 +1,41 @@
-use crate::mappers::block::extract_block_metrics;
-use crate::pb::bitcoin::analytics::v1::BlockMetrics;
-use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, Transaction, Vin, Vout};
+use super::fixtures::{GENESIS_HASH, PREV_TXID};
+use crate::mappers::block::{extract_block_metrics, proof_of_work, witness_adoption};
+use crate::mappers::utxo::utxo_key;
+use crate::pb::bitcoin::analytics::v1::BlockTimestamp;
+use crate::pb::bitcoin::utxo::v1::UTXO;
+use crate::utils::compact;
+use crate::utils::pools::PoolDatabase;
+use std::collections::HashMap;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, ScriptPubKey, ScriptSig, Transaction, Vin, Vout};
 
 #[test]
 fn test_extract_block_metrics() {
+    // Output spent by the regular transaction, created in an earlier block
+    let prev_tx_id = PREV_TXID.to_string();
+    let mut store = HashMap::new();
+    store.insert(
+        utxo_key(&prev_tx_id, 0),
+        UTXO {
+            tx_id: prev_tx_id,
+            value: 150_000_000, // 1.5 BTC
+            ..Default::default()
+        },
+    );
+
     // Create a mock Bitcoin block
-    let mut block = Block {
+    let block = Block {
         height: 123456,
-        hash: vec![1, 2, 3, 4, 5],
+        hash: GENESIS_HASH.to_string(),
         size: 1000,
         weight: 4000,
         version: 1,
         bits: "1d00ffff".to_string(),
         nonce: 12345,
         time: 1620000000,
+        mediantime: 1619997000,
         difficulty: 1.0,
-        transaction_count: 2,
-        transactions: vec![
+        n_tx: 2,
+        tx: vec![
             // Coinbase transaction
             Transaction {
                 txid: "coinbase_tx".to_string(),
@@ -28,7 +47,7 @@ fn test_extract_block_metrics() {
                 ],
                 vout: vec![
                     Vout {
-                        value: 50.0, // 50 BTC
+                        value: 50.5, // 50 BTC subsidy + 0.5 BTC fees
                         ..Default::default()
                     }
                 ],
@@ -39,6 +58,7 @@ fn test_extract_block_metrics() {
                 txid: "regular_tx".to_string(),
                 vin: vec![
                     Vin {
+                        txid: PREV_TXID.to_string(),
                         txinwitness: vec!["010203".to_string()], // SegWit input
                         ..Default::default()
                     }
@@ -46,7 +66,10 @@ fn test_extract_block_metrics() {
                 vout: vec![
                     Vout {
                         value: 1.0, // 1 BTC
-                        script_pub_key_type: "witness_v1_taproot".to_string(),
+                        script_pub_key: Some(ScriptPubKey {
+                            r#type: "witness_v1_taproot".to_string(),
+                            ..Default::default()
+                        }),
                         ..Default::default()
                     }
                 ],
@@ -57,20 +80,224 @@ fn test_extract_block_metrics() {
     };
 
     // Extract metrics
-    let result = extract_block_metrics(&block);
+    let previous = BlockTimestamp {
+        number: 123455,
+        time: 1619999400,
+        median_time: 1619996500,
+    };
+    let result = extract_block_metrics(&block, &store, Some(&previous), &PoolDatabase::default(), None);
     assert!(result.is_ok());
     
     let metrics = result.unwrap();
     
     // Verify basic block properties
     assert_eq!(metrics.number, 123456);
-    assert_eq!(metrics.hash, "0102030405");
+    assert_eq!(metrics.hash, GENESIS_HASH);
     assert_eq!(metrics.timestamp, 1620000000);
     assert_eq!(metrics.size, 1000);
     assert_eq!(metrics.weight, 4000);
     assert_eq!(metrics.tx_count, 2);
+    assert_eq!(metrics.miner, "Unknown");
+    assert_eq!(metrics.miner_match_method, "UNKNOWN");
     
     // Verify calculated metrics
-    assert_eq!(metrics.block_time, 600); // Our placeholder value
-    assert!(metrics.block_reward > 0); // Should have a reward
+    assert_eq!(metrics.block_time, 600);
+    assert_eq!(metrics.block_interval, 600);
+    assert_eq!(metrics.median_time, 1619997000);
+    assert_eq!(metrics.block_reward, 50_0000_0000);
+    assert!(metrics.fees_resolved);
+    assert_eq!(metrics.total_fees, 50_000_000);
+    assert_eq!(metrics.coinbase_fees, 50_000_000);
+}
+
+#[test]
+fn test_total_fees_unset_when_unresolved() {
+    let block = Block {
+        hash: GENESIS_HASH.to_string(),
+        height: 840_000,
+        bits: "17034219".to_string(),
+        tx: vec![
+            Transaction {
+                vin: vec![Vin {
+                    coinbase: "03404d0c".to_string(),
+                    ..Default::default()
+                }],
+                vout: vec![Vout {
+                    value: 3.3, // 3.125 BTC subsidy + 0.175 BTC fees
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            Transaction {
+                vin: vec![Vin {
+                    // Spent output missing from the store
+                    txid: "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16".to_string(),
+                    ..Default::default()
+                }],
+                vout: vec![Vout {
+                    value: 1.0,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let metrics = extract_block_metrics(&block, &HashMap::new(), None, &PoolDatabase::default(), None).unwrap();
+    assert_eq!(metrics.block_reward, 3_1250_0000);
+    // The coinbase-derived fees are kept apart from the unresolved total
+    assert_eq!(metrics.coinbase_fees, 17_500_000);
+    assert!(!metrics.fees_resolved);
+    assert_eq!(metrics.total_fees, 0);
+}
+
+#[test]
+fn test_block_time_with_timestamp_going_backwards() {
+    let block = Block {
+        hash: GENESIS_HASH.to_string(),
+        height: 100_001,
+        bits: "1b04864c".to_string(),
+        time: 1_293_623_000,
+        ..Default::default()
+    };
+
+    let previous = BlockTimestamp {
+        number: 100_000,
+        time: 1_293_623_863,
+        ..Default::default()
+    };
+    let metrics = extract_block_metrics(&block, &HashMap::new(), Some(&previous), &PoolDatabase::default(), None).unwrap();
+    assert_eq!(metrics.block_interval, -863);
+    assert_eq!(metrics.block_time, 0);
+
+    // Timestamps of a block that isn't the parent are ignored
+    let unrelated = BlockTimestamp {
+        number: 99_000,
+        ..previous
+    };
+    let metrics = extract_block_metrics(&block, &HashMap::new(), Some(&unrelated), &PoolDatabase::default(), None).unwrap();
+    assert_eq!(metrics.block_interval, 0);
+}
+
+#[test]
+fn test_target_work_and_chainwork() {
+    let block = Block {
+        hash: GENESIS_HASH.to_string(),
+        bits: "1d00ffff".to_string(),
+        chainwork: "0000000000000000000000000000000000000000000000000000000100010001".to_string(),
+        ..Default::default()
+    };
+
+    let chainwork = compact::parse_u256(&block.chainwork).unwrap();
+    let metrics = extract_block_metrics(&block, &HashMap::new(), None, &PoolDatabase::default(), Some(chainwork)).unwrap();
+    assert_eq!(metrics.bits, 0x1d00ffff);
+    assert_eq!(metrics.target, "00000000ffff0000000000000000000000000000000000000000000000000000");
+    assert_eq!(metrics.work, "0000000000000000000000000000000000000000000000000000000100010001");
+    assert_eq!(metrics.chainwork, block.chainwork);
+
+    // Chain work is left empty when it isn't tracked
+    let metrics = extract_block_metrics(&block, &HashMap::new(), None, &PoolDatabase::default(), None).unwrap();
+    assert_eq!(metrics.chainwork, "");
+
+    // store_chainwork adds the same work
+    assert_eq!(compact::to_hex_u256(proof_of_work(&block).unwrap().work), metrics.work);
+
+    // Bits that don't decode to a valid target are an error
+    let invalid = Block { bits: "04923456".to_string(), ..block };
+    assert!(proof_of_work(&invalid).is_err());
+    assert!(extract_block_metrics(&invalid, &HashMap::new(), None, &PoolDatabase::default(), None).is_err());
+}
+
+#[test]
+fn test_witness_adoption() {
+    let sig = "304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee01";
+    let pubkey = "025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357";
+    let input = |script_sig: String, witness: Vec<String>| Vin {
+        txid: PREV_TXID.to_string(),
+        script_sig: Some(ScriptSig {
+            hex: script_sig,
+            ..Default::default()
+        }),
+        txinwitness: witness,
+        ..Default::default()
+    };
+    let output = |script_hex: String| Vout {
+        script_pub_key: Some(ScriptPubKey {
+            hex: script_hex,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let p2pkh = format!("76a914{}88ac", "11".repeat(20));
+    let p2wpkh = format!("0014{}", "22".repeat(20));
+    let p2tr = format!("5120{}", "33".repeat(32));
+
+    let block = Block {
+        hash: GENESIS_HASH.to_string(),
+        bits: "1d00ffff".to_string(),
+        tx: vec![
+            // Coinbase with its witness reserved value
+            Transaction {
+                vin: vec![Vin {
+                    coinbase: "01020304".to_string(),
+                    txinwitness: vec!["00".repeat(32)],
+                    ..Default::default()
+                }],
+                vout: vec![output(p2wpkh.clone())],
+                size: 150,
+                weight: 492,
+                ..Default::default()
+            },
+            // Legacy P2PKH spend
+            Transaction {
+                vin: vec![input(format!("47{}21{}", sig, pubkey), vec![])],
+                vout: vec![output(p2pkh.clone())],
+                size: 191,
+                weight: 764,
+                ..Default::default()
+            },
+            // Nested P2SH-P2WPKH and native P2WPKH spends: 113 stripped bytes, 109 witness bytes
+            Transaction {
+                vin: vec![
+                    input(format!("16{}", p2wpkh), vec![sig.to_string(), pubkey.to_string()]),
+                    input(String::new(), vec![sig.to_string(), pubkey.to_string()]),
+                ],
+                vout: vec![output(p2wpkh), output(p2pkh)],
+                size: 222,
+                weight: 561,
+                ..Default::default()
+            },
+            // Taproot key-path spend creating a P2TR output
+            Transaction {
+                vin: vec![input(String::new(), vec!["ab".repeat(64)])],
+                vout: vec![output(p2tr)],
+                size: 150,
+                weight: 396,
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let adoption = witness_adoption(&block).unwrap();
+    assert_eq!(adoption.segwit_txs, 3);
+    assert_eq!(adoption.nested_segwit_txs, 1);
+    assert_eq!(adoption.taproot_txs, 1);
+    assert_eq!(adoption.inputs, 4);
+    assert_eq!(adoption.segwit_inputs, 3);
+    assert_eq!(adoption.nested_segwit_inputs, 1);
+    assert_eq!(adoption.taproot_inputs, 1);
+    assert_eq!(adoption.outputs, 5);
+    assert_eq!(adoption.segwit_outputs, 3);
+    assert_eq!(adoption.taproot_outputs, 1);
+    assert_eq!(adoption.witness_bytes, 36 + 109 + 68);
+    assert_eq!(adoption.weight_saved(), 3 * (36 + 109 + 68));
+
+    let metrics = extract_block_metrics(&block, &HashMap::new(), None, &PoolDatabase::default(), None).unwrap();
+    assert_eq!(metrics.segwit_percent, 75.0);
+    assert_eq!(metrics.taproot_percent, 25.0);
+    assert_eq!(metrics.nested_segwit_input_count, 1);
+    assert_eq!(metrics.taproot_output_count, 1);
+    assert_eq!(metrics.witness_weight_saved, 639);
 }
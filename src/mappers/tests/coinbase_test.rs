@@ -0,0 +1,120 @@
+use super::fixtures::GENESIS_COINBASE_TXID;
+use crate::mappers::coinbase::{ascii_tags, bip34_height, extract_coinbase_info};
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, ScriptPubKey, Transaction, Vin, Vout};
+
+/// scriptSig of the genesis coinbase: bits, extranonce 4 and The Times headline
+const GENESIS_SCRIPT_SIG: &str = "04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73";
+const ROOT: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+const HASH: &str = "2222222222222222222222222222222222222222222222222222222222222222";
+
+fn coinbase_block(height: i64, script_sig: &str, outputs: &[&str]) -> Block {
+    Block {
+        height,
+        tx: vec![Transaction {
+            txid: GENESIS_COINBASE_TXID.to_string(),
+            vin: vec![Vin {
+                coinbase: script_sig.to_string(),
+                txinwitness: vec!["00".repeat(32)],
+                ..Default::default()
+            }],
+            vout: outputs
+                .iter()
+                .map(|hex| Vout {
+                    script_pub_key: Some(ScriptPubKey {
+                        hex: hex.to_string(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_genesis_coinbase() {
+    let block = coinbase_block(0, GENESIS_SCRIPT_SIG, &[]);
+    let info = extract_coinbase_info(&block).unwrap().unwrap();
+    assert_eq!(info.tx_id, GENESIS_COINBASE_TXID);
+    // Before BIP34 the first push is arbitrary, here the bits
+    assert_eq!(info.bip34_height, 0x1d00ffff);
+    assert!(!info.bip34_height_mismatch);
+    assert_eq!(info.extranonce, "04");
+    // The length of the headline push, 0x45, reads as a leading 'E'
+    assert_eq!(info.tags, vec!["EThe Times 03/Jan/2009 Chancellor on brink of second bailout for banks"]);
+    assert_eq!(info.witness_commitment, "");
+    assert!(info.merged_mining.is_empty());
+}
+
+#[test]
+fn test_bip34_height() {
+    // Block 227931, the first one enforcing BIP34
+    assert_eq!(bip34_height(&hex::decode("035b7a03").unwrap()), Some(227_931));
+    // Block 840000
+    assert_eq!(bip34_height(&hex::decode("0340d10c").unwrap()), Some(840_000));
+    // Small heights are pushed with OP_1 to OP_16 on test networks
+    assert_eq!(bip34_height(&[0x5a]), Some(10));
+    assert_eq!(bip34_height(&hex::decode("025b00").unwrap()), None);
+    assert_eq!(bip34_height(&[]), None);
+}
+
+#[test]
+fn test_modern_coinbase() {
+    let script_sig = format!(
+        "0340d10c{}{}{}",
+        "080000000000000001",
+        "2cfabe6d6d".to_owned() + ROOT + "04000000" + "2a000000",
+        hex::encode(b"\x0d/Foundry USA/")
+    );
+    let witness_commitment = format!("6a24aa21a9ed{}", HASH);
+    let rsk = format!("6a29{}{}", hex::encode(b"RSKBLOCK:"), ROOT);
+    let block = coinbase_block(840_000, &script_sig, &["76a914000000000000000000000000000000000000000088ac", &rsk, &witness_commitment]);
+
+    let info = extract_coinbase_info(&block).unwrap().unwrap();
+    assert_eq!(info.block_height, 840_000);
+    assert_eq!(info.bip34_height, 840_000);
+    assert!(!info.bip34_height_mismatch);
+    assert_eq!(info.extranonce, "0000000000000001");
+    assert_eq!(info.tags, vec!["/Foundry USA/"]);
+    assert_eq!(info.witness_commitment, HASH);
+    assert_eq!(info.witness_reserved_value, "00".repeat(32));
+
+    assert_eq!(info.merged_mining.len(), 2);
+    assert_eq!(info.merged_mining[0].protocol, "AUXPOW");
+    assert_eq!(info.merged_mining[0].hash, ROOT);
+    assert_eq!(info.merged_mining[0].output_index, -1);
+    assert_eq!(info.merged_mining[0].merkle_size, 4);
+    assert_eq!(info.merged_mining[0].nonce, 42);
+    assert_eq!(info.merged_mining[1].protocol, "RSK");
+    assert_eq!(info.merged_mining[1].hash, ROOT);
+    assert_eq!(info.merged_mining[1].output_index, 1);
+}
+
+#[test]
+fn test_small_height_and_truncated_push() {
+    // Height 10 pushed with OP_10, then the extranonce and a tag whose push overruns the script
+    let script_sig = format!("5a04deadbeef{}", hex::encode(b"\x14/Mined by test/"));
+    let block = coinbase_block(10, &script_sig, &[]);
+    let info = extract_coinbase_info(&block).unwrap().unwrap();
+    assert_eq!(info.bip34_height, 10);
+    assert_eq!(info.extranonce, "deadbeef");
+    assert_eq!(info.tags, vec!["/Mined by test/"]);
+}
+
+#[test]
+fn test_bip34_height_mismatch() {
+    let block = coinbase_block(840_001, "0340d10c", &[]);
+    let info = extract_coinbase_info(&block).unwrap().unwrap();
+    assert!(info.bip34_height_mismatch);
+
+    let block = coinbase_block(840_001, "", &[]);
+    assert!(extract_coinbase_info(&block).unwrap().is_none());
+}
+
+#[test]
+fn test_ascii_tags() {
+    assert_eq!(ascii_tags(b"\x01Mined by AntPool\x00\x02ab\xffslush"), vec!["Mined by AntPool", "slush"]);
+    assert!(ascii_tags(&[0x00, 0x01, 0x02]).is_empty());
+}
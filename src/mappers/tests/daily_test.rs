@@ -0,0 +1,144 @@
+use super::fixtures::{GENESIS_COINBASE_TXID, PREV_TXID, SEGWIT_TXID, SEGWIT_WTXID};
+use crate::mappers::daily::{
+    block_daily_totals, day_start, finalize_day, ACTIVE_ADDRESSES, BLOCK_COUNT, BLOCK_INTERVAL,
+    BLOCK_SIZE, FEES, SEGWIT_TX_COUNT, TAPROOT_TX_COUNT, TX_COUNT, TX_VOLUME, VSIZE,
+};
+use crate::mappers::utxo::utxo_key;
+use crate::pb::bitcoin::analytics::v1::BlockTimestamp;
+use crate::pb::bitcoin::utxo::v1::UTXO;
+use std::collections::HashMap;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, Transaction, Vin, Vout};
+
+#[test]
+fn test_day_start() {
+    assert_eq!(day_start(1_690_000_000), 1_689_984_000);
+    assert_eq!(day_start(1_689_984_000), 1_689_984_000);
+    assert_eq!(day_start(1_689_983_999), 1_689_897_600);
+    assert_eq!(day_start(0), 0);
+}
+
+#[test]
+fn test_block_daily_totals() {
+    let prev_tx_id = PREV_TXID.to_string();
+    let mut store = HashMap::new();
+    store.insert(
+        utxo_key(&prev_tx_id, 0),
+        UTXO {
+            tx_id: prev_tx_id,
+            value: 100_000,
+            ..Default::default()
+        },
+    );
+
+    let block = Block {
+        height: 2,
+        time: 1_690_000_600,
+        size: 1_000,
+        tx: vec![
+            Transaction {
+                txid: GENESIS_COINBASE_TXID.to_string(),
+                hash: GENESIS_COINBASE_TXID.to_string(),
+                vin: vec![Vin {
+                    coinbase: "0340350c".to_string(),
+                    ..Default::default()
+                }],
+                vout: vec![Vout {
+                    value: 6.25001,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            Transaction {
+                txid: SEGWIT_TXID.to_string(),
+                hash: SEGWIT_WTXID.to_string(),
+                vsize: 141,
+                vin: vec![Vin {
+                    txid: PREV_TXID.to_string(),
+                    txinwitness: vec!["3044".to_string(), "02aa".to_string()],
+                    ..Default::default()
+                }],
+                vout: vec![Vout {
+                    value: 0.00099,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            // Spends an output missing from the store, so its fee is unknown
+            Transaction {
+                txid: "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16".to_string(),
+                hash: "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16".to_string(),
+                vsize: 200,
+                vin: vec![Vin {
+                    txid: PREV_TXID.to_string(),
+                    vout: 1,
+                    ..Default::default()
+                }],
+                vout: vec![Vout {
+                    value: 0.0005,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let previous = BlockTimestamp {
+        number: 1,
+        time: 1_690_000_000,
+        median_time: 0,
+    };
+
+    let totals: HashMap<_, _> = block_daily_totals(&block, &store, Some(&previous))
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert_eq!(totals[BLOCK_COUNT], 1);
+    assert_eq!(totals[BLOCK_INTERVAL], 600);
+    assert_eq!(totals[BLOCK_SIZE], 1_000);
+    assert_eq!(totals[TX_COUNT], 3);
+    assert_eq!(totals[TX_VOLUME], 149_000);
+    // The unresolved transaction is left out of the fee rate
+    assert_eq!(totals[FEES], 1_000);
+    assert_eq!(totals[VSIZE], 141);
+    assert_eq!(totals[SEGWIT_TX_COUNT], 1);
+    assert_eq!(totals[TAPROOT_TX_COUNT], 0);
+}
+
+#[test]
+fn test_finalize_day() {
+    let totals: HashMap<&str, i64> = HashMap::from([
+        (BLOCK_COUNT, 4),
+        (BLOCK_INTERVAL, 2_400),
+        (BLOCK_SIZE, 6_000_000),
+        (TX_COUNT, 10_000),
+        (TX_VOLUME, 5_000_000_000),
+        (FEES, 20_000_000),
+        (VSIZE, 4_000_000),
+        (SEGWIT_TX_COUNT, 9_000),
+        (TAPROOT_TX_COUNT, 2_500),
+        (ACTIVE_ADDRESSES, 12_345),
+    ]);
+
+    let metrics = finalize_day(
+        1_689_984_000,
+        |field| totals.get(field).copied().unwrap_or(0),
+        2.0e14,
+    );
+    assert_eq!(metrics.date, 1_689_984_000);
+    assert_eq!(metrics.avg_block_time, 600.0);
+    assert_eq!(metrics.total_tx_count, 10_000);
+    assert_eq!(metrics.total_tx_volume, 5_000_000_000);
+    assert_eq!(metrics.avg_block_size, 1_500_000);
+    assert_eq!(metrics.avg_tx_per_block, 2_500.0);
+    assert_eq!(metrics.avg_fee_rate, 5.0);
+    assert_eq!(metrics.mempool_tx_count, 0);
+    assert_eq!(metrics.segwit_tx_percent, 90.0);
+    assert_eq!(metrics.taproot_tx_percent, 25.0);
+    assert_eq!(metrics.avg_difficulty, 5.0e13);
+    assert_eq!(metrics.active_addresses, 12_345);
+
+    // An empty day doesn't divide by zero
+    let empty = finalize_day(0, |_| 0, 0.0);
+    assert_eq!(empty.avg_block_time, 0.0);
+    assert_eq!(empty.avg_fee_rate, 0.0);
+}
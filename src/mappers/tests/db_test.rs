@@ -0,0 +1,175 @@
+use crate::mappers::db::database_changes;
+use crate::pb::bitcoin::analytics::v1::{
+    AddressActivity, AddressActivityRecord, AddressActivityRecords, BlockMetrics, BlockMetricsRecord,
+    DeploymentPeriod, DeploymentPeriodRecord, DeploymentPeriodRecords, EpochMetrics, EpochMetricsRecord,
+    EpochMetricsRecords, Inscription, InscriptionRecord, InscriptionRecords, NetworkDailyMetrics, NetworkDailyMetricsRecord, NetworkDailyMetricsRecords, TransactionMetricsRecords,
+};
+use crate::pb::bitcoin::utxo::v1::{UTXORecord, UTXORecords, UTXO};
+use substreams_database_change::pb::database::table_change::{Operation, PrimaryKey};
+use substreams_database_change::pb::database::TableChange;
+
+fn field<'a>(change: &'a TableChange, name: &str) -> &'a str {
+    &change.fields.iter().find(|field| field.name == name).unwrap().new_value
+}
+
+fn composite_key(change: &TableChange) -> Vec<(&str, &str)> {
+    match &change.primary_key {
+        Some(PrimaryKey::CompositePk(key)) => {
+            let mut keys: Vec<_> = key.keys.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+            keys.sort();
+            keys
+        }
+        other => panic!("expected a composite key, got {:?}", other),
+    }
+}
+
+/// The only change of `table`, as changes aren't ordered across rows
+fn change<'a>(changes: &'a [TableChange], table: &str) -> &'a TableChange {
+    let mut matches = changes.iter().filter(|change| change.table == table);
+    let change = matches.next().unwrap();
+    assert!(matches.next().is_none(), "several changes of {}", table);
+    change
+}
+
+#[test]
+fn test_database_changes() {
+    let block = BlockMetricsRecord {
+        table: "blocks".to_string(),
+        block: Some(BlockMetrics {
+            number: 800_000,
+            hash: "00000000000000000002a7c4c1e48d76c5a37902165a270156b7a8d72728a054".to_string(),
+            timestamp: 1_690_168_629,
+            ..Default::default()
+        }),
+    };
+    let created = UTXO {
+        tx_id: "aa".to_string(),
+        vout_index: 1,
+        value: 5_000,
+        block_height: 800_000,
+        block_time: 1_690_168_629,
+        ..Default::default()
+    };
+    let spent = UTXO {
+        is_spent: true,
+        spent_in_tx_id: "bb".to_string(),
+        spent_at_block_height: 800_000,
+        ..created.clone()
+    };
+    let utxos = UTXORecords {
+        records: vec![
+            UTXORecord {
+                table: "bitcoin_utxos".to_string(),
+                utxo: Some(created),
+            },
+            UTXORecord {
+                table: "bitcoin_utxos".to_string(),
+                utxo: Some(spent),
+            },
+        ],
+    };
+    let activities = AddressActivityRecords {
+        records: vec![AddressActivityRecord {
+            table: "bitcoin_address_transactions".to_string(),
+            activity: Some(AddressActivity {
+                address: "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string(),
+                tx_hash: "bb".to_string(),
+                is_input: true,
+                io_index: 3,
+                ..Default::default()
+            }),
+        }],
+    };
+    let daily = NetworkDailyMetricsRecords {
+        records: vec![NetworkDailyMetricsRecord {
+            table: "bitcoin_daily_stats".to_string(),
+            metrics: Some(NetworkDailyMetrics {
+                date: 1_690_156_800,
+                ..Default::default()
+            }),
+        }],
+    };
+
+    let epochs = EpochMetricsRecords {
+        records: vec![EpochMetricsRecord {
+            table: "bitcoin_difficulty_epochs".to_string(),
+            metrics: Some(EpochMetrics {
+                epoch: 396,
+                bits_match: true,
+                ..Default::default()
+            }),
+        }],
+    };
+
+    let deployments = DeploymentPeriodRecords {
+        records: vec![DeploymentPeriodRecord {
+            table: "bitcoin_deployment_periods".to_string(),
+            period: Some(DeploymentPeriod {
+                deployment: "taproot".to_string(),
+                period: 341,
+                state: "STARTED".to_string(),
+                next_state: "LOCKED_IN".to_string(),
+                ..Default::default()
+            }),
+        }],
+    };
+
+    let inscriptions = InscriptionRecords {
+        records: vec![InscriptionRecord {
+            table: "bitcoin_inscriptions".to_string(),
+            inscription: Some(Inscription {
+                inscription_id: "bbi0".to_string(),
+                parents: vec!["aai0".to_string(), "aai1".to_string()],
+                ..Default::default()
+            }),
+        }],
+    };
+
+    let changes = database_changes(
+        &block,
+        &TransactionMetricsRecords::default(),
+        &utxos,
+        &activities,
+        &daily,
+        &epochs,
+        &deployments,
+        &inscriptions,
+    );
+    let changes = changes.table_changes;
+    assert_eq!(changes.len(), 7);
+
+    let block = change(&changes, "blocks");
+    assert_eq!(block.operation, Operation::Create as i32);
+    assert_eq!(block.primary_key, Some(PrimaryKey::Pk("800000".to_string())));
+    assert_eq!(field(block, "timestamp"), "2023-07-24T03:17:09Z");
+
+    // The output created and spent in the block is a single row, inserted with its spend
+    let utxo = change(&changes, "bitcoin_utxos");
+    assert_eq!(utxo.operation, Operation::Create as i32);
+    assert_eq!(composite_key(utxo), vec![("tx_id", "aa"), ("vout_index", "1")]);
+    assert_eq!(field(utxo, "is_spent"), "1");
+    assert_eq!(field(utxo, "value"), "5000");
+    assert_eq!(field(utxo, "spent_in_tx_id"), "bb");
+
+    let activity = change(&changes, "bitcoin_address_transactions");
+    assert_eq!(
+        composite_key(activity),
+        vec![("block_height", "0"), ("io_index", "3"), ("is_input", "1"), ("tx_id", "bb")]
+    );
+
+    let daily = change(&changes, "bitcoin_daily_stats");
+    assert_eq!(daily.primary_key, Some(PrimaryKey::Pk("2023-07-24".to_string())));
+    assert_eq!(field(daily, "date"), "2023-07-24");
+
+    let epoch = change(&changes, "bitcoin_difficulty_epochs");
+    assert_eq!(epoch.primary_key, Some(PrimaryKey::Pk("396".to_string())));
+    assert_eq!(field(epoch, "bits_match"), "1");
+
+    let deployment = change(&changes, "bitcoin_deployment_periods");
+    assert_eq!(composite_key(deployment), vec![("deployment", "taproot"), ("period", "341")]);
+    assert_eq!(field(deployment, "next_state"), "LOCKED_IN");
+
+    let inscription = change(&changes, "bitcoin_inscriptions");
+    assert_eq!(inscription.primary_key, Some(PrimaryKey::Pk("bbi0".to_string())));
+    assert_eq!(field(inscription, "parents"), "aai0,aai1");
+}
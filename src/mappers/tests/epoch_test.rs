@@ -0,0 +1,76 @@
+use crate::mappers::epoch::{
+    boundary_field, epoch_metrics, expected_bits, BLOCKS_PER_EPOCH, FIRST_BLOCK, LAST_BLOCK, TARGET_TIMESPAN,
+};
+use crate::pb::bitcoin::analytics::v1::EpochBoundary;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+#[test]
+fn test_boundary_field() {
+    assert_eq!(boundary_field(0), Some(FIRST_BLOCK));
+    assert_eq!(boundary_field(2015), Some(LAST_BLOCK));
+    assert_eq!(boundary_field(2016), Some(FIRST_BLOCK));
+    assert_eq!(boundary_field(2017), None);
+    assert_eq!(boundary_field(840_000), None);
+}
+
+#[test]
+fn test_expected_bits() {
+    // Vectors from Bitcoin Core's pow_tests, as (bits, first block time, last block time, next bits)
+
+    // Block #32256, the first retarget that changed the difficulty
+    assert_eq!(expected_bits(0x1d00ffff, 1262152739 - 1261130161).unwrap(), 0x1d00d86a);
+    // Block #2016, capped at the pow limit
+    assert_eq!(expected_bits(0x1d00ffff, 1233061996 - 1231006505).unwrap(), 0x1d00ffff);
+    // Block #68544, timespan clamped to a quarter of two weeks
+    assert_eq!(expected_bits(0x1c05a3f4, 1279297671 - 1279008237).unwrap(), 0x1c0168fd);
+    // Block #46368, timespan clamped to four times two weeks
+    assert_eq!(expected_bits(0x1c387f6f, 1269211443 - 1263163443).unwrap(), 0x1d00e1fd);
+}
+
+#[test]
+fn test_expected_bits_unchanged_on_target_timespan() {
+    assert_eq!(expected_bits(0x17034219, TARGET_TIMESPAN).unwrap(), 0x17034219);
+}
+
+#[test]
+fn test_epoch_metrics() {
+    let first = EpochBoundary {
+        number: 30_240,
+        time: 1261130161,
+        bits: 0x1d00ffff,
+    };
+    let last = EpochBoundary {
+        number: 32_255,
+        time: 1262152739,
+        bits: 0x1d00ffff,
+    };
+    let next = Block {
+        height: 32_256,
+        bits: "1d00d86a".to_string(),
+        ..Default::default()
+    };
+
+    let metrics = epoch_metrics(&first, &last, &next).unwrap();
+    assert_eq!(metrics.epoch, 15);
+    assert_eq!(metrics.start_height, 30_240);
+    assert_eq!(metrics.end_height, 32_255);
+    assert_eq!(metrics.actual_timespan, 1_022_578);
+    assert_eq!(metrics.target_timespan, 1_209_600);
+    assert_eq!(metrics.expected_bits, 0x1d00d86a);
+    assert_eq!(metrics.next_bits, 0x1d00d86a);
+    assert!(metrics.bits_match);
+
+    // Difficulty went from 1 to about 1.18
+    assert!((metrics.adjustment_percent - 18.29).abs() < 0.01);
+    let expected_hashrate = 4_294_967_296.0 * (BLOCKS_PER_EPOCH - 1) as f64 / 1_022_578.0;
+    assert!((metrics.hashrate - expected_hashrate).abs() < 1.0);
+
+    // A header that doesn't follow the retarget rules is flagged
+    let next = Block {
+        bits: "1d00ffff".to_string(),
+        ..next
+    };
+    let metrics = epoch_metrics(&first, &last, &next).unwrap();
+    assert!(!metrics.bits_match);
+    assert_eq!(metrics.adjustment_percent, 0.0);
+}
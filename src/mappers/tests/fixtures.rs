@@ -0,0 +1,11 @@
+//! Ids shared by the mapper tests
+
+/// Outpoint txid spent by the BIP143 native P2WPKH sample transaction
+pub const PREV_TXID: &str = "8ac60eb9575db5b2d987e29f301b5b819ea83a5c6579d282d189cc04b8e151ef";
+/// Txid and wtxid of the BIP143 native P2WPKH sample transaction
+pub const SEGWIT_TXID: &str = "e8151a2af31c368a35053ddd4bdb285a8595c769a3ad83e0fa02314a602d4609";
+pub const SEGWIT_WTXID: &str = "c36c38370907df2324d9ce9d149d191192f338b37665a82e78e76a12c909b762";
+/// Txid of the genesis coinbase, which has no witness
+pub const GENESIS_COINBASE_TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+/// Hash of the genesis block, in display byte order
+pub const GENESIS_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
@@ -0,0 +1,170 @@
+use crate::mappers::graph::entity_changes;
+use crate::mappers::utxo::UNRESOLVED_SCRIPT_TYPE;
+use crate::pb::bitcoin::analytics::v1::{
+    AddressActivity, AddressActivityRecord, AddressActivityRecords, BlockMetrics, BlockMetricsRecord,
+    TransactionMetrics, TransactionMetricsRecord, TransactionMetricsRecords,
+};
+use crate::pb::bitcoin::utxo::v1::{UTXORecord, UTXORecords, UTXO};
+use substreams_entity_change::pb::entity::entity_change::Operation;
+use substreams_entity_change::pb::entity::value::Typed;
+use substreams_entity_change::pb::entity::EntityChange;
+
+fn field(change: &EntityChange, name: &str) -> Option<Typed> {
+    change
+        .fields
+        .iter()
+        .find(|field| field.name == name)
+        .and_then(|field| field.new_value.clone())
+        .and_then(|value| value.typed)
+}
+
+/// The change of an entity, as changes aren't ordered across entities
+fn change<'a>(changes: &'a [EntityChange], entity: &str, id: &str) -> &'a EntityChange {
+    changes
+        .iter()
+        .find(|change| change.entity == entity && change.id == id)
+        .unwrap_or_else(|| panic!("no change of {} {}", entity, id))
+}
+
+#[test]
+fn test_entity_changes() {
+    let block = BlockMetricsRecord {
+        table: "blocks".to_string(),
+        block: Some(BlockMetrics {
+            number: 800_000,
+            miner: "AntPool".to_string(),
+            miner_match_method: "COINBASE_TAG".to_string(),
+            difficulty: 53_911_173_001_054.59,
+            ..Default::default()
+        }),
+    };
+    let transactions = TransactionMetricsRecords {
+        records: vec![TransactionMetricsRecord {
+            table: "bitcoin_transactions".to_string(),
+            transaction: Some(TransactionMetrics {
+                hash: "bb".to_string(),
+                block_number: 800_000,
+                fee: 1_000,
+                ..Default::default()
+            }),
+        }],
+    };
+    let utxos = UTXORecords {
+        records: vec![
+            UTXORecord {
+                table: "bitcoin_utxos".to_string(),
+                utxo: Some(UTXO {
+                    tx_id: "aa".to_string(),
+                    vout_index: 0,
+                    address: "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string(),
+                    block_height: 799_000,
+                    is_spent: true,
+                    spent_in_tx_id: "bb".to_string(),
+                    spent_at_block_height: 800_000,
+                    ..Default::default()
+                }),
+            },
+            UTXORecord {
+                table: "bitcoin_utxos".to_string(),
+                utxo: Some(UTXO {
+                    tx_id: "bb".to_string(),
+                    vout_index: 0,
+                    script_type: "OP_RETURN".to_string(),
+                    block_height: 800_000,
+                    ..Default::default()
+                }),
+            },
+            // Spent output missing from the store
+            UTXORecord {
+                table: "bitcoin_utxos".to_string(),
+                utxo: Some(UTXO {
+                    tx_id: "cc".to_string(),
+                    vout_index: 1,
+                    script_type: UNRESOLVED_SCRIPT_TYPE.to_string(),
+                    is_spent: true,
+                    spent_in_tx_id: "bb".to_string(),
+                    spent_at_block_height: 800_000,
+                    ..Default::default()
+                }),
+            },
+        ],
+    };
+    let activities = AddressActivityRecords {
+        records: vec![AddressActivityRecord {
+            table: "bitcoin_address_transactions".to_string(),
+            activity: Some(AddressActivity {
+                address: "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string(),
+                tx_hash: "bb".to_string(),
+                is_input: true,
+                ..Default::default()
+            }),
+        }],
+    };
+
+    let changes = entity_changes(&block, &transactions, &utxos, &activities).entity_changes;
+    let mut entities: Vec<_> = changes.iter().map(|change| (change.entity.as_str(), change.id.as_str())).collect();
+    entities.sort();
+    assert_eq!(
+        entities,
+        vec![
+            ("Address", "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"),
+            ("Block", "800000"),
+            ("Pool", "AntPool"),
+            ("Transaction", "bb:800000"),
+            ("UTXO", "aa:0"),
+            ("UTXO", "bb:0"),
+        ]
+    );
+
+    let block = change(&changes, "Block", "800000");
+    assert_eq!(field(block, "pool"), Some(Typed::String("AntPool".to_string())));
+    assert_eq!(field(block, "difficulty"), Some(Typed::Bigdecimal("53911173001054.59".to_string())));
+    let transaction = change(&changes, "Transaction", "bb:800000");
+    assert_eq!(field(transaction, "hash"), Some(Typed::String("bb".to_string())));
+    assert_eq!(field(transaction, "block"), Some(Typed::String("800000".to_string())));
+    assert_eq!(field(transaction, "fee"), Some(Typed::Bigint("1000".to_string())));
+    assert_eq!(field(change(&changes, "Address", "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"), "pool"), None);
+
+    // A spent output is upserted with its spend, an output without address has no relation and
+    // an unresolved spent output, linked to no creation block, is left out
+    let spent = change(&changes, "UTXO", "aa:0");
+    assert_eq!(spent.operation, Operation::Update as i32);
+    assert_eq!(field(spent, "transaction"), Some(Typed::String("aa:799000".to_string())));
+    assert_eq!(field(spent, "spentInTransaction"), Some(Typed::String("bb:800000".to_string())));
+    assert_eq!(field(spent, "block"), Some(Typed::String("799000".to_string())));
+    let created = change(&changes, "UTXO", "bb:0");
+    assert_eq!(created.operation, Operation::Create as i32);
+    assert_eq!(field(created, "address"), None);
+    assert_eq!(field(created, "spentAtBlock"), None);
+}
+
+#[test]
+fn test_address_pool_is_the_block_pool() {
+    let block = BlockMetricsRecord {
+        table: "blocks".to_string(),
+        block: Some(BlockMetrics {
+            number: 800_000,
+            miner: "AntPool".to_string(),
+            miner_match_method: "PAYOUT_ADDRESS".to_string(),
+            ..Default::default()
+        }),
+    };
+    let pool_address = |address: &str, pool: &str| AddressActivityRecord {
+        table: "bitcoin_address_transactions".to_string(),
+        activity: Some(AddressActivity {
+            address: address.to_string(),
+            address_category: "MINING_POOL".to_string(),
+            address_tag: pool.to_string(),
+            ..Default::default()
+        }),
+    };
+    let activities = AddressActivityRecords {
+        records: vec![pool_address("1A", "AntPool"), pool_address("1B", "F2Pool")],
+    };
+
+    let changes = entity_changes(&block, &Default::default(), &Default::default(), &activities).entity_changes;
+    assert_eq!(field(change(&changes, "Address", "1A"), "pool"), Some(Typed::String("AntPool".to_string())));
+    // F2Pool has no Pool entity in this block, so the relation would dangle
+    assert_eq!(field(change(&changes, "Address", "1B"), "pool"), None);
+    assert!(changes.iter().all(|change| change.entity != "Pool" || change.id == "AntPool"));
+}
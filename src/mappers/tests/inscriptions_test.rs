@@ -0,0 +1,182 @@
+use super::fixtures::SEGWIT_TXID;
+use crate::mappers::inscriptions::{decode_payload, extract_inscriptions, inscription_id, parse_envelopes};
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, Transaction, Vin};
+
+/// Direct push of up to 75 bytes
+fn push(bytes: &[u8]) -> Vec<u8> {
+    let mut script = vec![bytes.len() as u8];
+    script.extend_from_slice(bytes);
+    script
+}
+
+/// `OP_FALSE OP_IF "ord" <items> OP_ENDIF`
+fn envelope(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut script = vec![0x00, 0x63];
+    script.extend(push(b"ord"));
+    items.iter().for_each(|item| script.extend(item));
+    script.push(0x68);
+    script
+}
+
+/// Tapscript `<x-only key> OP_CHECKSIG` followed by `envelopes`
+fn tapscript(envelopes: &[Vec<u8>]) -> Vec<u8> {
+    let mut script = push(&[0xcd; 32]);
+    script.push(0xac);
+    envelopes.iter().for_each(|envelope| script.extend(envelope));
+    script
+}
+
+/// Inscription id value of `SEGWIT_TXID` with an index, txid bytes in internal order
+fn id_value(index: &[u8]) -> Vec<u8> {
+    let mut value = hex::decode(SEGWIT_TXID).unwrap();
+    value.reverse();
+    value.extend_from_slice(index);
+    value
+}
+
+#[test]
+fn test_parse_envelopes() {
+    // OP_1 stands for a one-byte push of 1, the content type tag
+    let text = envelope(&[vec![0x51], push(b"text/plain"), vec![0x00], push(b"Hello")]);
+    let script = tapscript(&[text.clone(), envelope(&[])]);
+    let envelopes = parse_envelopes(&script);
+    assert_eq!(envelopes.len(), 2);
+    assert_eq!(envelopes[0], vec![vec![1], b"text/plain".to_vec(), vec![], b"Hello".to_vec()]);
+    assert!(envelopes[1].is_empty());
+
+    // OP_TRUE instead of OP_FALSE, a non-push opcode and a missing OP_ENDIF are not envelopes
+    let mut not_false = text.clone();
+    not_false[0] = 0x51;
+    assert!(parse_envelopes(&tapscript(&[not_false])).is_empty());
+    assert!(parse_envelopes(&tapscript(&[envelope(&[vec![0xac]])])).is_empty());
+    assert!(parse_envelopes(&text[..text.len() - 1]).is_empty());
+
+    // A truncated push discards every envelope of the script
+    let mut truncated = tapscript(&[text]);
+    truncated.extend([0x05, 0x01]);
+    assert!(parse_envelopes(&truncated).is_empty());
+}
+
+#[test]
+fn test_decode_payload() {
+    let parent = id_value(&[0x01]);
+    let delegate = id_value(&[]);
+    let payload = vec![
+        vec![1],
+        b"text/plain;charset=utf-8".to_vec(),
+        vec![2],
+        vec![0xe8, 0x03],
+        vec![3],
+        parent,
+        vec![5],
+        vec![0xa1, 0x61],
+        vec![5],
+        vec![0x61, 0x01],
+        vec![7],
+        b"brc-20".to_vec(),
+        vec![9],
+        b"br".to_vec(),
+        vec![11],
+        delegate,
+        vec![],
+        b"Hello".to_vec(),
+        b", world".to_vec(),
+    ];
+
+    let inscription = decode_payload(&payload);
+    assert_eq!(inscription.content_type, "text/plain;charset=utf-8");
+    assert_eq!(inscription.content_encoding, "br");
+    assert!(inscription.has_pointer);
+    assert_eq!(inscription.pointer, 1000);
+    assert_eq!(inscription.parents, vec![format!("{}i1", SEGWIT_TXID)]);
+    assert_eq!(inscription.metadata, "a1616101");
+    assert_eq!(inscription.metaprotocol, "brc-20");
+    assert_eq!(inscription.delegate, format!("{}i0", SEGWIT_TXID));
+    assert!(inscription.has_body);
+    assert_eq!(inscription.body_length, 12);
+    assert!(!inscription.duplicate_field);
+    assert!(!inscription.incomplete_field);
+    assert!(!inscription.unrecognized_even_field);
+}
+
+#[test]
+fn test_decode_payload_flags() {
+    // The content type given twice keeps its first value, tag 4 is an unknown even tag
+    let inscription = decode_payload(&[
+        vec![1],
+        b"image/png".to_vec(),
+        vec![1],
+        b"text/html".to_vec(),
+        vec![4],
+        vec![0x01],
+        vec![1],
+    ]);
+    assert_eq!(inscription.content_type, "image/png");
+    assert!(inscription.duplicate_field);
+    assert!(inscription.unrecognized_even_field);
+    assert!(inscription.incomplete_field);
+    assert!(!inscription.has_body);
+
+    // An empty value at a value position does not start the body, an odd unknown tag is ignored
+    let inscription = decode_payload(&[vec![1], vec![], vec![13], vec![0x01]]);
+    assert!(!inscription.has_body);
+    assert!(!inscription.unrecognized_even_field);
+
+    // A pointer with non-zero bytes past 64 bits is ignored
+    let inscription = decode_payload(&[vec![2], vec![0, 0, 0, 0, 0, 0, 0, 0, 1]]);
+    assert!(!inscription.has_pointer);
+}
+
+#[test]
+fn test_inscription_id() {
+    assert_eq!(inscription_id(&id_value(&[])), Some(format!("{}i0", SEGWIT_TXID)));
+    assert_eq!(inscription_id(&id_value(&[0x00, 0x01])), Some(format!("{}i256", SEGWIT_TXID)));
+    // The index must be minimal
+    assert_eq!(inscription_id(&id_value(&[0x01, 0x00])), None);
+    assert_eq!(inscription_id(&id_value(&[1, 2, 3, 4, 5])), None);
+    assert_eq!(inscription_id(&[0u8; 31]), None);
+}
+
+#[test]
+fn test_extract_inscriptions() {
+    let control_block = format!("c0{}", "ef".repeat(32));
+    let reveal = |envelopes: &[Vec<u8>]| Vin {
+        txid: SEGWIT_TXID.to_string(),
+        txinwitness: vec!["ab".repeat(64), hex::encode(tapscript(envelopes)), control_block.clone()],
+        ..Default::default()
+    };
+    let text = envelope(&[push(&[1]), push(b"text/plain"), vec![0x00], push(b"Hello")]);
+
+    let block = Block {
+        height: 780_000,
+        time: 1_677_000_000,
+        tx: vec![Transaction {
+            txid: SEGWIT_TXID.to_string(),
+            vin: vec![
+                reveal(&[text.clone(), text.clone()]),
+                // Key-path spends carry no tapscript
+                Vin {
+                    txid: SEGWIT_TXID.to_string(),
+                    txinwitness: vec!["ab".repeat(64)],
+                    ..Default::default()
+                },
+                reveal(&[text]),
+            ],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let records = extract_inscriptions(&block).unwrap();
+    assert_eq!(records.len(), 3);
+    assert!(records.iter().all(|record| record.table == "bitcoin_inscriptions"));
+
+    let inscriptions: Vec<_> = records.iter().map(|record| record.inscription.as_ref().unwrap()).collect();
+    let positions: Vec<(u32, u32)> = inscriptions.iter().map(|i| (i.input_index, i.envelope_index)).collect();
+    assert_eq!(positions, vec![(0, 0), (0, 1), (2, 2)]);
+    assert_eq!(inscriptions[2].inscription_id, format!("{}i2", SEGWIT_TXID));
+    assert_eq!(inscriptions[2].tx_id, SEGWIT_TXID);
+    assert_eq!(inscriptions[2].block_height, 780_000);
+    assert_eq!(inscriptions[2].content_type, "text/plain");
+    assert_eq!(inscriptions[2].body_length, 5);
+}
@@ -0,0 +1,17 @@
+mod address_test;
+mod block_test;
+mod coinbase_test;
+mod daily_test;
+mod db_test;
+mod epoch_test;
+mod fixtures;
+mod graph_test;
+mod inscriptions_test;
+#[cfg(feature = "sat-ranges")]
+mod sats_test;
+mod spend_test;
+mod taproot_test;
+mod transaction_test;
+mod utxo_test;
+mod validation_test;
+mod versionbits_test;
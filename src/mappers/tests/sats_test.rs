@@ -0,0 +1,239 @@
+use super::fixtures::{PREV_TXID, SEGWIT_TXID};
+use crate::mappers::sats::{assign_sat_ranges, first_sat, rarity, sat_position, start_height, Rarity, Span};
+use crate::mappers::utxo::utxo_key;
+use crate::pb::bitcoin::analytics::v1::{RareSat, SatRange};
+use crate::pb::bitcoin::utxo::v1::UTXO;
+use std::collections::HashMap;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, Transaction, Vin, Vout};
+
+const COINBASE_TXID: &str = "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098";
+const SECOND_TXID: &str = "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d";
+const UNTRACKED_TXID: &str = "9f96ade4b41d5433f4eda31e1738ec2b36f6e7d1420d94a6af99801a88f7f7ff";
+
+/// Total number of sats ever mined
+const SUPPLY: u64 = 2_099_999_997_690_000;
+
+fn outputs(values_btc: &[f64]) -> Vec<Vout> {
+    values_btc
+        .iter()
+        .map(|value| Vout {
+            value: *value,
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn spend(txid: &str, vout: u32) -> Vin {
+    Vin {
+        txid: txid.to_string(),
+        vout,
+        ..Default::default()
+    }
+}
+
+fn coinbase(values_btc: &[f64]) -> Transaction {
+    Transaction {
+        txid: COINBASE_TXID.to_string(),
+        vin: vec![Vin {
+            coinbase: "0101".to_string(),
+            ..Default::default()
+        }],
+        vout: outputs(values_btc),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_first_sat_and_position() {
+    assert_eq!(first_sat(0), 0);
+    assert_eq!(first_sat(1), 50_0000_0000);
+    assert_eq!(first_sat(210_000), 1_050_000_000_000_000);
+    assert_eq!(first_sat(210_001), 1_050_000_000_000_000 + 25_0000_0000);
+
+    assert_eq!(sat_position(0), Some((0, 0)));
+    assert_eq!(sat_position(50_0000_0001), Some((1, 1)));
+    assert_eq!(sat_position(first_sat(840_000) + 7), Some((840_000, 7)));
+    assert_eq!(sat_position(SUPPLY - 1), Some((6_929_999, 0)));
+    assert_eq!(sat_position(SUPPLY), None);
+}
+
+#[test]
+fn test_rarity() {
+    assert_eq!(rarity(0), Rarity::Mythic);
+    assert_eq!(rarity(1), Rarity::Common);
+    assert_eq!(rarity(first_sat(1)), Rarity::Uncommon);
+    assert_eq!(rarity(first_sat(2016)), Rarity::Rare);
+    assert_eq!(rarity(first_sat(210_000)), Rarity::Epic);
+    assert_eq!(rarity(first_sat(840_000)), Rarity::Epic);
+    assert_eq!(rarity(first_sat(1_260_000)), Rarity::Legendary);
+    assert_eq!(rarity(first_sat(1_260_000) + 1), Rarity::Common);
+    assert_eq!(rarity(SUPPLY), Rarity::Common);
+    assert_eq!(Rarity::Legendary.as_str(), "legendary");
+}
+
+#[test]
+fn test_start_height_params() {
+    assert_eq!(start_height("").unwrap(), 0);
+    assert_eq!(start_height(r#"{"start_height": 840000}"#).unwrap(), 840_000);
+    assert!(start_height("840000x").is_err());
+}
+
+#[test]
+fn test_coinbase_receives_subsidy_range() {
+    let block = Block {
+        height: 2016,
+        tx: vec![coinbase(&[30.0, 20.0])],
+        ..Default::default()
+    };
+    let records = assign_sat_ranges(&block, &HashMap::new(), &mut HashMap::new()).unwrap();
+    assert_eq!(records.len(), 2);
+
+    let start = first_sat(2016);
+    let first = records[0].output.as_ref().unwrap();
+    assert_eq!(first.tx_id, COINBASE_TXID);
+    assert_eq!(first.value, 30_0000_0000);
+    assert_eq!(first.ranges, vec![SatRange { start, end: start + 30_0000_0000 }]);
+    assert_eq!(
+        first.rare_sats,
+        vec![RareSat {
+            sat: start,
+            rarity: "rare".to_string(),
+            offset: 0,
+        }]
+    );
+
+    let second = records[1].output.as_ref().unwrap();
+    assert_eq!(second.vout_index, 1);
+    assert_eq!(second.ranges, vec![SatRange { start: start + 30_0000_0000, end: start + 50_0000_0000 }]);
+    assert!(second.rare_sats.is_empty());
+}
+
+#[test]
+fn test_ranges_carried_first_in_first_out() {
+    // Two tracked outputs, the first holding the first sat of block 1
+    let mut carried = HashMap::new();
+    carried.insert(utxo_key(PREV_TXID, 0), vec![Span::Known { start: 50_0000_0000, end: 50_0000_0600 }]);
+    carried.insert(utxo_key(PREV_TXID, 1), vec![Span::Known { start: 70_0000_0000, end: 70_0000_0400 }]);
+    // An output created before tracking started, resolved from the UTXO store
+    let mut store = HashMap::new();
+    store.insert(
+        utxo_key(UNTRACKED_TXID, 0),
+        UTXO {
+            value: 300,
+            ..Default::default()
+        },
+    );
+
+    let block = Block {
+        height: 100,
+        tx: vec![
+            coinbase(&[50.00000250]),
+            // 1,000 sats in, 800 out: the last 200 sats are fees
+            Transaction {
+                txid: SEGWIT_TXID.to_string(),
+                vin: vec![spend(PREV_TXID, 0), spend(PREV_TXID, 1)],
+                vout: outputs(&[0.00000700, 0.00000100]),
+                ..Default::default()
+            },
+            // 300 untracked sats in, 250 out: 50 sats of unknown fees
+            Transaction {
+                txid: SECOND_TXID.to_string(),
+                vin: vec![spend(UNTRACKED_TXID, 0)],
+                vout: outputs(&[0.00000250]),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let records = assign_sat_ranges(&block, &store, &mut carried).unwrap();
+    let outputs: Vec<_> = records.iter().map(|record| record.output.as_ref().unwrap()).collect();
+    // The output holding only untracked sats has no record
+    assert_eq!(outputs.len(), 3);
+
+    // The first output takes the first input, then the head of the second
+    assert_eq!(outputs[0].tx_id, SEGWIT_TXID);
+    assert_eq!(
+        outputs[0].ranges,
+        vec![
+            SatRange { start: 50_0000_0000, end: 50_0000_0600 },
+            SatRange { start: 70_0000_0000, end: 70_0000_0100 },
+        ]
+    );
+    assert_eq!(outputs[0].rare_sats[0].rarity, "uncommon");
+    assert_eq!(outputs[1].ranges, vec![SatRange { start: 70_0000_0100, end: 70_0000_0200 }]);
+
+    // The coinbase claims the subsidy, the tracked fees, then 50 of the untracked fees
+    let subsidy_start = first_sat(100);
+    let coinbase = outputs[2];
+    assert_eq!(coinbase.tx_id, COINBASE_TXID);
+    assert_eq!(
+        coinbase.ranges,
+        vec![
+            SatRange { start: subsidy_start, end: subsidy_start + 50_0000_0000 },
+            SatRange { start: 70_0000_0200, end: 70_0000_0400 },
+        ]
+    );
+    assert_eq!(coinbase.unknown_sats, 50);
+    assert_eq!(coinbase.value, 50_0000_0250);
+
+    // Spent outputs leave the carried set, new outputs join it
+    assert!(!carried.contains_key(&utxo_key(PREV_TXID, 1)));
+    assert!(!carried.contains_key(&utxo_key(PREV_TXID, 0)));
+    assert_eq!(carried[&utxo_key(SECOND_TXID, 0)], vec![Span::Unknown(250)]);
+    assert_eq!(carried[&utxo_key(SEGWIT_TXID, 1)], vec![Span::Known { start: 70_0000_0100, end: 70_0000_0200 }]);
+}
+
+#[test]
+fn test_ranges_carried_across_blocks() {
+    // The caller keeps the carried spans from one block to the next
+    let mut carried = HashMap::new();
+    let mined = Block {
+        height: 100,
+        tx: vec![coinbase(&[50.0])],
+        ..Default::default()
+    };
+    assign_sat_ranges(&mined, &HashMap::new(), &mut carried).unwrap();
+
+    // A later block spends the coinbase output, leaving 10,000 sats of fees
+    let block = Block {
+        height: 200,
+        tx: vec![
+            Transaction {
+                txid: SECOND_TXID.to_string(),
+                ..coinbase(&[50.0001])
+            },
+            Transaction {
+                txid: SEGWIT_TXID.to_string(),
+                vin: vec![spend(COINBASE_TXID, 0)],
+                vout: outputs(&[10.0, 39.9999]),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let records = assign_sat_ranges(&block, &HashMap::new(), &mut carried).unwrap();
+    let outputs: Vec<_> = records.iter().map(|record| record.output.as_ref().unwrap()).collect();
+    assert_eq!(outputs.len(), 3);
+
+    let mined_start = first_sat(100);
+    assert_eq!(outputs[0].tx_id, SEGWIT_TXID);
+    assert_eq!(outputs[0].ranges, vec![SatRange { start: mined_start, end: mined_start + 10_0000_0000 }]);
+    assert_eq!(outputs[0].rare_sats[0].rarity, "uncommon");
+    assert_eq!(
+        outputs[1].ranges,
+        vec![SatRange { start: mined_start + 10_0000_0000, end: mined_start + 49_9999_0000 }]
+    );
+
+    let subsidy_start = first_sat(200);
+    assert_eq!(outputs[2].tx_id, SECOND_TXID);
+    assert_eq!(
+        outputs[2].ranges,
+        vec![
+            SatRange { start: subsidy_start, end: subsidy_start + 50_0000_0000 },
+            SatRange { start: mined_start + 49_9999_0000, end: mined_start + 50_0000_0000 },
+        ]
+    );
+    assert!(outputs.iter().all(|output| output.unknown_sats == 0));
+    assert!(!carried.contains_key(&utxo_key(COINBASE_TXID, 0)));
+}
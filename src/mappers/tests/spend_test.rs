@@ -0,0 +1,127 @@
+use super::fixtures::PREV_TXID;
+use crate::mappers::spend::{classify_input, classify_spend, SpendType};
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{ScriptSig, Vin};
+
+/// Signature and public key of the BIP143 native P2WPKH sample input
+const SIG: &str = "304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee01";
+const PUBKEY: &str = "025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357";
+
+fn bytes(hex_str: &str) -> Vec<u8> {
+    hex::decode(hex_str).unwrap()
+}
+
+/// scriptSig made of direct pushes
+fn pushes(items: &[&str]) -> Vec<u8> {
+    items
+        .iter()
+        .flat_map(|item| {
+            let item = bytes(item);
+            let mut push = vec![item.len() as u8];
+            push.extend(item);
+            push
+        })
+        .collect()
+}
+
+fn witness(items: &[&str]) -> Vec<Vec<u8>> {
+    items.iter().map(|item| bytes(item)).collect()
+}
+
+#[test]
+fn test_legacy_spends() {
+    assert_eq!(classify_spend(&pushes(&[SIG]), &[]), SpendType::P2pk);
+    assert_eq!(classify_spend(&pushes(&[SIG, PUBKEY]), &[]), SpendType::P2pkh);
+
+    // OP_0 <sig> <sig>
+    let mut bare_multisig = vec![0x00];
+    bare_multisig.extend(pushes(&[SIG, SIG]));
+    assert_eq!(classify_spend(&bare_multisig, &[]), SpendType::Multisig);
+
+    // OP_0 <sig> <sig> <1-of-1 redeem script>
+    let redeem_script = format!("5121{}51ae", PUBKEY);
+    let mut p2sh_multisig = vec![0x00];
+    p2sh_multisig.extend(pushes(&[SIG, SIG, &redeem_script]));
+    assert_eq!(classify_spend(&p2sh_multisig, &[]), SpendType::P2sh);
+}
+
+#[test]
+fn test_nested_segwit_spends() {
+    let p2wpkh_program = pushes(&["001479091972186c449eb1ded22b78e40d009bdf0089"]);
+    assert_eq!(classify_spend(&p2wpkh_program, &witness(&[SIG, PUBKEY])), SpendType::P2shP2wpkh);
+
+    let p2wsh_program = pushes(&["0020a16b5755f7f6f96dbd65f5f0d6ab9418b89af4b1f14a1bb8a09062c35f0dcb54"]);
+    let witness_script = format!("5121{}51ae", PUBKEY);
+    assert_eq!(
+        classify_spend(&p2wsh_program, &witness(&["", SIG, &witness_script])),
+        SpendType::P2shP2wsh
+    );
+
+    // A redeem script that is not a version 0 program
+    assert_eq!(classify_spend(&pushes(&[PUBKEY]), &witness(&[SIG, PUBKEY])), SpendType::Unknown);
+}
+
+#[test]
+fn test_native_segwit_spends() {
+    assert_eq!(classify_spend(&[], &witness(&[SIG, PUBKEY])), SpendType::P2wpkh);
+
+    let witness_script = format!("5221{}21{}52ae", PUBKEY, PUBKEY);
+    assert_eq!(classify_spend(&[], &witness(&["", SIG, SIG, &witness_script])), SpendType::P2wsh);
+}
+
+#[test]
+fn test_taproot_spends() {
+    let schnorr = "ab".repeat(64);
+    let schnorr_with_sighash = format!("{}01", "ab".repeat(64));
+    let annex = "50aabb";
+    assert_eq!(classify_spend(&[], &witness(&[&schnorr])), SpendType::TaprootKeyPath);
+    assert_eq!(classify_spend(&[], &witness(&[&schnorr_with_sighash])), SpendType::TaprootKeyPath);
+    assert_eq!(classify_spend(&[], &witness(&[&schnorr, annex])), SpendType::TaprootKeyPath);
+
+    // <sig> <x-only pubkey OP_CHECKSIG> <control block with one merkle node>
+    let tapscript = format!("20{}ac", "cd".repeat(32));
+    let control_block = format!("c1{}{}", "ef".repeat(32), "12".repeat(32));
+    assert_eq!(
+        classify_spend(&[], &witness(&[&schnorr, &tapscript, &control_block])),
+        SpendType::TaprootScriptPath
+    );
+    assert_eq!(
+        classify_spend(&[], &witness(&[&schnorr, &tapscript, &control_block, annex])),
+        SpendType::TaprootScriptPath
+    );
+
+    // A control block with a partial merkle node is a P2WSH witness script
+    let truncated = format!("c0{}{}", "ef".repeat(32), "12".repeat(31));
+    assert_eq!(classify_spend(&[], &witness(&[&schnorr, &tapscript, &truncated])), SpendType::P2wsh);
+}
+
+#[test]
+fn test_unclassified_spends() {
+    assert_eq!(classify_spend(&[], &[]), SpendType::Unknown);
+    // Non-push opcode in the scriptSig
+    assert_eq!(classify_spend(&bytes("76"), &[]), SpendType::Unknown);
+    // A public key alone is not a signature
+    assert_eq!(classify_spend(&pushes(&[PUBKEY, PUBKEY]), &witness(&[SIG])), SpendType::Unknown);
+}
+
+#[test]
+fn test_classify_input() {
+    let vin = Vin {
+        txid: PREV_TXID.to_string(),
+        vout: 1,
+        script_sig: Some(ScriptSig {
+            hex: String::new(),
+            ..Default::default()
+        }),
+        txinwitness: vec![SIG.to_string(), PUBKEY.to_string()],
+        ..Default::default()
+    };
+    let spend_type = classify_input(&vin).unwrap();
+    assert_eq!(spend_type, SpendType::P2wpkh);
+    assert_eq!(spend_type.name(), "P2WPKH");
+
+    let invalid = Vin {
+        txinwitness: vec!["zz".to_string()],
+        ..vin
+    };
+    assert!(classify_input(&invalid).is_err());
+}
@@ -0,0 +1,137 @@
+use super::fixtures::{PREV_TXID, SEGWIT_TXID};
+use crate::mappers::taproot::{extract_taproot_metrics, parse_control_block, parse_witness, TaprootSpend};
+use crate::mappers::utxo::utxo_key;
+use crate::pb::bitcoin::utxo::v1::UTXO;
+use std::collections::HashMap;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, ScriptPubKey, Transaction, Vin, Vout};
+
+fn bytes(hex_str: &str) -> Vec<u8> {
+    hex::decode(hex_str).unwrap()
+}
+
+fn schnorr() -> String {
+    "ab".repeat(64)
+}
+
+fn tapscript() -> String {
+    format!("20{}ac", "cd".repeat(32))
+}
+
+/// Control block of a tapscript leaf with odd output key parity and `depth` merkle nodes
+fn control_block(depth: usize) -> String {
+    format!("c1{}{}", "ef".repeat(32), "12".repeat(32 * depth))
+}
+
+#[test]
+fn test_parse_control_block() {
+    let encoded = bytes(&control_block(2));
+    let decoded = parse_control_block(&encoded).unwrap();
+    assert_eq!(decoded.leaf_version, 0xc0);
+    assert!(decoded.output_key_parity);
+    assert_eq!(decoded.internal_key, bytes(&"ef".repeat(32)).as_slice());
+    assert_eq!(decoded.merkle_path.len(), 2);
+
+    assert_eq!(parse_control_block(&bytes(&control_block(0))).unwrap().merkle_path.len(), 0);
+    assert!(parse_control_block(&bytes(&control_block(128))).is_some());
+    assert!(parse_control_block(&bytes(&control_block(129))).is_none());
+    assert!(parse_control_block(&bytes(&"c0".repeat(32))).is_none());
+    assert!(parse_control_block(&bytes(&"c0".repeat(34))).is_none());
+}
+
+#[test]
+fn test_parse_witness() {
+    let key_path = vec![bytes(&schnorr())];
+    let parsed = parse_witness(&key_path).unwrap();
+    assert!(matches!(parsed.spend, TaprootSpend::KeyPath { signature } if signature.len() == 64));
+    assert_eq!(parsed.annex, None);
+
+    let with_annex = vec![bytes(&schnorr()), bytes("50aabb")];
+    let parsed = parse_witness(&with_annex).unwrap();
+    assert!(matches!(parsed.spend, TaprootSpend::KeyPath { .. }));
+    assert_eq!(parsed.annex, Some(bytes("50aabb").as_slice()));
+
+    let script_path = vec![bytes(&schnorr()), bytes(&tapscript()), bytes(&control_block(1))];
+    let TaprootSpend::ScriptPath { tapscript: script, control_block: decoded } = parse_witness(&script_path).unwrap().spend
+    else {
+        panic!("expected a script-path spend");
+    };
+    assert_eq!(script, bytes(&tapscript()).as_slice());
+    assert_eq!(decoded.merkle_path.len(), 1);
+
+    // A lone 0x50 item is a key-path signature, not an annex
+    assert!(matches!(parse_witness(&[bytes("50")]).unwrap().spend, TaprootSpend::KeyPath { .. }));
+    assert!(parse_witness(&[]).is_none());
+    assert!(parse_witness(&[bytes(&tapscript()), bytes("c0")]).is_none());
+}
+
+#[test]
+fn test_extract_taproot_metrics() {
+    let p2tr = format!("5120{}", "77".repeat(32));
+    let mut store = HashMap::new();
+    for vout_index in 0..3 {
+        store.insert(
+            utxo_key(PREV_TXID, vout_index),
+            UTXO {
+                tx_id: PREV_TXID.to_string(),
+                vout_index,
+                script_type: if vout_index == 2 { "P2WSH" } else { "P2TR" }.to_string(),
+                ..Default::default()
+            },
+        );
+    }
+
+    let input = |vout: u32, witness: Vec<String>| Vin {
+        txid: PREV_TXID.to_string(),
+        vout,
+        txinwitness: witness,
+        ..Default::default()
+    };
+    let block = Block {
+        height: 800_000,
+        time: 1_690_000_000,
+        tx: vec![Transaction {
+            txid: SEGWIT_TXID.to_string(),
+            vin: vec![
+                input(0, vec![schnorr(), "50aabb".to_string()]),
+                input(1, vec![schnorr(), tapscript(), control_block(3)]),
+                // Spends a P2WSH output whose witness looks like a key-path spend
+                input(2, vec![schnorr()]),
+                // Missing from the store: inferred from the witness
+                input(3, vec![schnorr()]),
+            ],
+            vout: vec![Vout {
+                script_pub_key: Some(ScriptPubKey {
+                    hex: p2tr,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let metrics = extract_taproot_metrics(&block, &store).unwrap();
+    assert_eq!(metrics.block_height, 800_000);
+    assert_eq!(metrics.p2tr_outputs, 1);
+    assert_eq!(metrics.key_path_spends, 2);
+    assert_eq!(metrics.script_path_spends, 1);
+    assert_eq!(metrics.annex_spends, 1);
+
+    let indexes: Vec<u32> = metrics.inputs.iter().map(|input| input.input_index).collect();
+    assert_eq!(indexes, vec![0, 1, 3]);
+
+    let key_path = &metrics.inputs[0];
+    assert_eq!(key_path.tx_id, SEGWIT_TXID);
+    assert!(!key_path.script_path);
+    assert!(key_path.has_annex);
+    assert_eq!(key_path.annex, "50aabb");
+
+    let script_path = &metrics.inputs[1];
+    assert!(script_path.script_path);
+    assert!(!script_path.has_annex);
+    assert_eq!(script_path.leaf_version, 0xc0);
+    assert_eq!(script_path.merkle_depth, 3);
+    assert_eq!(script_path.internal_key, "ef".repeat(32));
+    assert_eq!(script_path.tapscript, tapscript());
+}
@@ -0,0 +1,167 @@
+use super::fixtures::{GENESIS_COINBASE_TXID, PREV_TXID, SEGWIT_TXID, SEGWIT_WTXID};
+use crate::mappers::transaction::extract_transaction_metrics;
+use crate::mappers::utxo::utxo_key;
+use crate::pb::bitcoin::utxo::v1::UTXO;
+use std::collections::HashMap;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, ScriptPubKey, Transaction, Vin, Vout};
+
+#[test]
+fn test_extract_transaction_metrics() {
+    let prev_tx_id = PREV_TXID.to_string();
+    let mut store = HashMap::new();
+    store.insert(
+        utxo_key(&prev_tx_id, 0),
+        UTXO {
+            tx_id: prev_tx_id,
+            value: 100_000,
+            ..Default::default()
+        },
+    );
+
+    let block = Block {
+        height: 800_000,
+        time: 1_690_000_000,
+        tx: vec![
+            Transaction {
+                txid: GENESIS_COINBASE_TXID.to_string(),
+                hash: GENESIS_COINBASE_TXID.to_string(),
+                vin: vec![Vin {
+                    coinbase: "0340350c".to_string(),
+                    ..Default::default()
+                }],
+                vout: vec![Vout {
+                    value: 6.25001,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            Transaction {
+                txid: SEGWIT_TXID.to_string(),
+                hash: SEGWIT_WTXID.to_string(),
+                size: 222,
+                vsize: 141,
+                weight: 561,
+                version: 2,
+                locktime: 799_999,
+                vin: vec![Vin {
+                    txid: PREV_TXID.to_string(),
+                    txinwitness: vec!["3044".to_string(), "02aa".to_string()],
+                    ..Default::default()
+                }],
+                vout: vec![Vout {
+                    value: 0.00099,
+                    script_pub_key: Some(ScriptPubKey {
+                        hex: "0014751e76e8199196d454941c45d1b3a323f1433bd6".to_string(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let records = extract_transaction_metrics(&block, &store).unwrap();
+    assert_eq!(records.len(), 2);
+    assert!(records.iter().all(|record| record.table == "bitcoin_transactions"));
+
+    let coinbase = records[0].transaction.as_ref().unwrap();
+    assert!(coinbase.is_coinbase);
+    assert_eq!(coinbase.tx_type, "COINBASE");
+    assert_eq!(coinbase.fee, 0);
+    assert!(coinbase.inputs_resolved);
+    assert_eq!(coinbase.output_value, 625_001_000);
+    assert_eq!(coinbase.hash, GENESIS_COINBASE_TXID);
+    assert_eq!(coinbase.wtxid, GENESIS_COINBASE_TXID);
+
+    let tx = records[1].transaction.as_ref().unwrap();
+    assert_eq!(tx.hash, SEGWIT_TXID);
+    assert_eq!(tx.wtxid, SEGWIT_WTXID);
+    assert_eq!(tx.block_number, 800_000);
+    assert_eq!(tx.block_timestamp, 1_690_000_000);
+    assert_eq!(tx.input_count, 1);
+    assert_eq!(tx.output_count, 1);
+    assert!(tx.inputs_resolved);
+    assert_eq!(tx.input_value, 100_000);
+    assert_eq!(tx.output_value, 99_000);
+    assert_eq!(tx.fee, 1_000);
+    assert!((tx.fee_rate - 1_000.0 / 141.0).abs() < 1e-9);
+    assert_eq!(tx.size, 222);
+    assert_eq!(tx.weight, 561);
+    assert_eq!(tx.version, 2);
+    assert_eq!(tx.locktime, 799_999);
+    assert!(tx.has_witness);
+    assert!(!tx.is_coinbase);
+    assert_eq!(tx.tx_type, "SEGWIT");
+}
+
+#[test]
+fn test_unresolved_input_leaves_value_and_fee_unset() {
+    let prev_tx_id = PREV_TXID.to_string();
+    let mut store = HashMap::new();
+    store.insert(
+        utxo_key(&prev_tx_id, 0),
+        UTXO {
+            tx_id: prev_tx_id,
+            value: 100_000,
+            ..Default::default()
+        },
+    );
+    let spend = |vout: u32| Vin {
+        txid: PREV_TXID.to_string(),
+        vout,
+        ..Default::default()
+    };
+    let block = Block {
+        tx: vec![Transaction {
+            txid: SEGWIT_TXID.to_string(),
+            hash: SEGWIT_WTXID.to_string(),
+            vsize: 141,
+            // The second spent output isn't in the store
+            vin: vec![spend(0), spend(1)],
+            vout: vec![Vout {
+                value: 0.00099,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let records = extract_transaction_metrics(&block, &store).unwrap();
+    let tx = records[0].transaction.as_ref().unwrap();
+    assert!(!tx.inputs_resolved);
+    assert_eq!(tx.input_value, 0);
+    assert_eq!(tx.fee, 0);
+    assert_eq!(tx.fee_rate, 0.0);
+    assert_eq!(tx.output_value, 99_000);
+}
+
+#[test]
+fn test_taproot_spend_to_segwit_output() {
+    // Key-path spend paying to a P2WPKH output
+    let block = Block {
+        tx: vec![Transaction {
+            txid: SEGWIT_TXID.to_string(),
+            hash: SEGWIT_WTXID.to_string(),
+            vin: vec![Vin {
+                txid: PREV_TXID.to_string(),
+                txinwitness: vec!["ab".repeat(64)],
+                ..Default::default()
+            }],
+            vout: vec![Vout {
+                script_pub_key: Some(ScriptPubKey {
+                    hex: "0014751e76e8199196d454941c45d1b3a323f1433bd6".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let records = extract_transaction_metrics(&block, &HashMap::new()).unwrap();
+    assert_eq!(records[0].transaction.as_ref().unwrap().tx_type, "TAPROOT");
+}
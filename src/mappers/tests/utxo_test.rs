@@ -0,0 +1,198 @@
+use super::fixtures::{PREV_TXID, SEGWIT_TXID, SEGWIT_WTXID};
+use crate::mappers::utxo::{
+    calculate_token_balances, extract_transaction_utxos, is_utxo_spent, process_utxos, resolve_prevouts, utxo_key,
+};
+use crate::pb::bitcoin::utxo::v1::UTXO;
+use crate::utils::amount::Amount;
+use std::collections::HashMap;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, ScriptPubKey, Transaction, Vin, Vout};
+
+/// The BIP143 native P2WPKH sample transaction, as reported by Firehose
+fn segwit_transaction() -> Transaction {
+    let p2pkh = |value: f64, script_hex: &str| Vout {
+        value,
+        script_pub_key: Some(ScriptPubKey {
+            hex: script_hex.to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    Transaction {
+        txid: SEGWIT_TXID.to_string(),
+        hash: SEGWIT_WTXID.to_string(),
+        vin: vec![
+            Vin {
+                txid: "9f96ade4b41d5433f4eda31e1738ec2b36f6e7d1420d94a6af99801a88f7f7ff".to_string(),
+                vout: 0,
+                ..Default::default()
+            },
+            Vin {
+                txid: PREV_TXID.to_string(),
+                vout: 1,
+                txinwitness: vec![
+                    "304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee01".to_string(),
+                    "025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357".to_string(),
+                ],
+                ..Default::default()
+            },
+        ],
+        vout: vec![
+            p2pkh(1.1234, "76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac"),
+            p2pkh(2.2345, "76a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac"),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_utxo_key_does_not_prefix_siblings() {
+    let key = utxo_key("abcd", 1);
+    assert_eq!(key, "abcd:0000000001");
+    assert!(!utxo_key("abcd", 10).starts_with(&key));
+}
+
+#[test]
+fn test_spent_utxo_resolved_from_store() {
+    let prev_tx_id = PREV_TXID.to_string();
+
+    // Output created in an earlier block
+    let mut store = HashMap::new();
+    store.insert(
+        utxo_key(&prev_tx_id, 1),
+        UTXO {
+            tx_id: prev_tx_id.clone(),
+            vout_index: 1,
+            value: 150_000,
+            script_type: "P2WPKH".to_string(),
+            address: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string(),
+            block_height: 700_000,
+            block_time: 1_631_000_000,
+            ..Default::default()
+        },
+    );
+
+    let block = Block {
+        height: 800_000,
+        time: 1_690_000_000,
+        tx: vec![Transaction {
+            txid: SEGWIT_TXID.to_string(),
+            hash: SEGWIT_WTXID.to_string(),
+            vin: vec![segwit_transaction().vin[1].clone()],
+            vout: vec![Vout {
+                script_pub_key: Some(ScriptPubKey {
+                    hex: "0014751e76e8199196d454941c45d1b3a323f1433bd6".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let records = process_utxos(&block, &store).unwrap();
+    assert_eq!(records.len(), 2);
+
+    let spent = records[0].utxo.as_ref().unwrap();
+    assert_eq!(spent.tx_id, prev_tx_id);
+    assert_eq!(spent.vout_index, 1);
+    assert_eq!(spent.value, 150_000);
+    assert_eq!(spent.script_type, "P2WPKH");
+    assert_eq!(spent.address, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+    assert_eq!(spent.block_height, 700_000);
+    assert_eq!(spent.block_time, 1_631_000_000);
+    assert!(spent.is_spent);
+    // Spends are linked by txid, never by the wtxid of a witness transaction
+    assert_eq!(spent.spent_in_tx_id, SEGWIT_TXID);
+    assert_eq!(spent.spent_in_input_index, 0);
+    assert_eq!(spent.spent_at_block_height, 800_000);
+    assert_eq!(spent.spent_at_block_time, 1_690_000_000);
+    assert_eq!(spent.spend_type, "P2WPKH");
+
+    let created = records[1].utxo.as_ref().unwrap();
+    assert_eq!(created.block_height, 800_000);
+    assert_eq!(created.script_type, "P2WPKH");
+    assert_eq!(created.address, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+    assert!(!created.is_spent);
+}
+
+#[test]
+fn test_segwit_outputs_keyed_by_txid() {
+    let utxos = extract_transaction_utxos(&segwit_transaction(), 800_000, 1_690_000_000).unwrap();
+    assert_eq!(utxos.len(), 2);
+    assert!(utxos.iter().all(|utxo| utxo.tx_id == SEGWIT_TXID));
+    assert_eq!(utxos[0].value, 112_340_000);
+    assert_eq!(utxos[1].value, 223_450_000);
+    assert_eq!(utxos[1].address, "16TZ8J6Q5iZKBWizWzFAYnrsaox5Z5aBRV");
+}
+
+#[test]
+fn test_calculate_token_balances() {
+    let mut utxos = extract_transaction_utxos(&segwit_transaction(), 800_000, 1_690_000_000).unwrap();
+    utxos.push(UTXO {
+        address: utxos[1].address.clone(),
+        value: 50_000,
+        ..Default::default()
+    });
+    // Outputs without an address aren't counted
+    utxos.push(UTXO {
+        value: 1_000,
+        ..Default::default()
+    });
+
+    let balances = calculate_token_balances(&utxos).unwrap();
+    assert_eq!(balances.len(), 2);
+    let (balance, count) = balances["16TZ8J6Q5iZKBWizWzFAYnrsaox5Z5aBRV"];
+    assert_eq!(balance.to_sat(), 223_500_000);
+    assert_eq!(count, 2);
+
+    // Balances can't exceed the supply cap
+    let overflow = vec![
+        UTXO {
+            address: utxos[0].address.clone(),
+            value: Amount::MAX_MONEY.to_sat(),
+            ..Default::default()
+        };
+        2
+    ];
+    assert!(calculate_token_balances(&overflow).is_err());
+}
+
+#[test]
+fn test_segwit_outputs_join_their_spends() {
+    // Outputs of the witness transaction, as stored by store_utxos
+    let block = Block {
+        height: 800_000,
+        time: 1_690_000_000,
+        tx: vec![segwit_transaction()],
+        ..Default::default()
+    };
+    let store: HashMap<_, _> = process_utxos(&block, &HashMap::new())
+        .unwrap()
+        .into_iter()
+        .filter_map(|record| record.utxo)
+        .filter(|utxo| !utxo.is_spent)
+        .map(|utxo| (utxo_key(&utxo.tx_id, utxo.vout_index), utxo))
+        .collect();
+
+    // A later transaction spends its second output by txid
+    let spending_tx = Transaction {
+        txid: "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16".to_string(),
+        vin: vec![Vin {
+            txid: SEGWIT_TXID.to_string(),
+            vout: 1,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    assert!(is_utxo_spent(&spending_tx, &SEGWIT_TXID.to_uppercase(), 1));
+    assert!(!is_utxo_spent(&spending_tx, SEGWIT_TXID, 0));
+    assert!(!is_utxo_spent(&spending_tx, "not a txid", 1));
+
+    let prevouts = resolve_prevouts(&spending_tx, 0, &store);
+    let prevout = prevouts[0].as_ref().expect("output of the witness transaction is resolved");
+    assert_eq!(prevout.tx_id, SEGWIT_TXID);
+    assert_eq!(prevout.vout_index, 1);
+    assert_eq!(prevout.value, 223_450_000);
+    assert_eq!(prevout.block_height, 800_000);
+}
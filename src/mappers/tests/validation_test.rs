@@ -0,0 +1,155 @@
+use super::fixtures::{GENESIS_COINBASE_TXID, GENESIS_HASH, SEGWIT_TXID, SEGWIT_WTXID};
+use crate::mappers::validation::validate_block;
+use crate::utils::hashes::{merkle_root, sha256d};
+use crate::utils::header::{check_proof_of_work, header_hash};
+use crate::utils::ids::{TxMerkleRoot, Txid, Wtxid};
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, ScriptPubKey, Transaction, Vin, Vout};
+
+/// Find a nonce meeting the target of the block and set its hash
+fn mine(mut block: Block) -> Block {
+    while !check_proof_of_work(header_hash(&block).unwrap(), 0x207fffff).unwrap() {
+        block.nonce += 1;
+    }
+    block.hash = header_hash(&block).unwrap().to_string();
+    block
+}
+
+fn segwit_block(commitment: Option<String>) -> Block {
+    let coinbase = Transaction {
+        txid: GENESIS_COINBASE_TXID.to_string(),
+        hash: GENESIS_COINBASE_TXID.to_string(),
+        vin: vec![Vin {
+            coinbase: "0340d10c".to_string(),
+            txinwitness: vec!["00".repeat(32)],
+            ..Default::default()
+        }],
+        vout: commitment
+            .into_iter()
+            .map(|commitment| Vout {
+                script_pub_key: Some(ScriptPubKey {
+                    hex: format!("6a24aa21a9ed{}", commitment),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let segwit = Transaction {
+        txid: SEGWIT_TXID.to_string(),
+        hash: SEGWIT_WTXID.to_string(),
+        vin: vec![Vin {
+            txid: GENESIS_COINBASE_TXID.to_string(),
+            txinwitness: vec!["00".to_string()],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let txids = [
+        Txid::from_hex(GENESIS_COINBASE_TXID).unwrap().to_byte_array(),
+        Txid::from_hex(SEGWIT_TXID).unwrap().to_byte_array(),
+    ];
+    // Regtest pow limit, so a valid nonce is found in a few tries
+    mine(Block {
+        height: 840_000,
+        version: 0x2000_0000,
+        previous_hash: GENESIS_HASH.to_string(),
+        merkle_root: TxMerkleRoot::from_byte_array(merkle_root(&txids).0).to_string(),
+        time: 1_713_571_767,
+        bits: "207fffff".to_string(),
+        tx: vec![coinbase, segwit],
+        ..Default::default()
+    })
+}
+
+/// Commitment to the sample wtxid, with a zero coinbase wtxid and witness reserved value
+fn expected_commitment() -> String {
+    let wtxids = [[0u8; 32], Wtxid::from_hex(SEGWIT_WTXID).unwrap().to_byte_array()];
+    let (witness_root, _) = merkle_root(&wtxids);
+    hex::encode(sha256d(&[witness_root, [0u8; 32]].concat()))
+}
+
+#[test]
+fn test_valid_block() {
+    let block = segwit_block(Some(expected_commitment()));
+    let validation = validate_block(&block);
+    assert!(validation.valid, "{:?}", validation.mismatches);
+    assert_eq!(validation.computed_merkle_root, block.merkle_root);
+    assert_eq!(validation.witness_commitment, expected_commitment());
+    assert_eq!(validation.computed_witness_commitment, expected_commitment());
+}
+
+#[test]
+fn test_genesis_block() {
+    let block = Block {
+        hash: GENESIS_HASH.to_string(),
+        version: 1,
+        merkle_root: GENESIS_COINBASE_TXID.to_string(),
+        time: 1_231_006_505,
+        bits: "1d00ffff".to_string(),
+        nonce: 2_083_236_893,
+        tx: vec![Transaction {
+            txid: GENESIS_COINBASE_TXID.to_string(),
+            hash: GENESIS_COINBASE_TXID.to_string(),
+            vin: vec![Vin {
+                coinbase: "04ffff001d010445".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let validation = validate_block(&block);
+    assert!(validation.valid, "{:?}", validation.mismatches);
+    assert_eq!(validation.computed_block_hash, GENESIS_HASH);
+    assert_eq!(validation.witness_commitment, "");
+}
+
+#[test]
+fn test_header_mismatches() {
+    // A hash that isn't the hash of the header
+    let mut block = segwit_block(Some(expected_commitment()));
+    block.hash = GENESIS_HASH.to_string();
+    assert_eq!(validate_block(&block).mismatches, vec!["BLOCK_HASH"]);
+
+    // A header whose hash doesn't meet its target
+    let mut block = segwit_block(Some(expected_commitment()));
+    block.bits = "1d00ffff".to_string();
+    assert_eq!(validate_block(&block).mismatches, vec!["BLOCK_HASH", "PROOF_OF_WORK"]);
+
+    let mut block = segwit_block(Some(expected_commitment()));
+    block.bits = "not bits".to_string();
+    assert_eq!(validate_block(&block).mismatches, vec!["INVALID_HEADER"]);
+}
+
+#[test]
+fn test_truncated_block() {
+    let mut block = segwit_block(Some(expected_commitment()));
+    block.tx.pop();
+    let validation = validate_block(&block);
+    assert!(!validation.valid);
+    assert_eq!(validation.mismatches, vec!["MERKLE_ROOT", "WITNESS_COMMITMENT"]);
+}
+
+#[test]
+fn test_witness_commitment_mismatches() {
+    let validation = validate_block(&segwit_block(Some("11".repeat(32))));
+    assert_eq!(validation.mismatches, vec!["WITNESS_COMMITMENT"]);
+
+    let validation = validate_block(&segwit_block(None));
+    assert_eq!(validation.mismatches, vec!["MISSING_WITNESS_COMMITMENT"]);
+
+    let mut block = segwit_block(Some(expected_commitment()));
+    block.tx[0].vin[0].txinwitness.clear();
+    assert_eq!(validate_block(&block).mismatches, vec!["MISSING_WITNESS_RESERVED_VALUE"]);
+}
+
+#[test]
+fn test_invalid_transaction_id() {
+    let mut block = segwit_block(Some(expected_commitment()));
+    block.tx[1].hash = "not a hash".to_string();
+    let validation = validate_block(&block);
+    assert_eq!(validation.mismatches, vec!["INVALID_TRANSACTION_ID"]);
+    assert_eq!(validation.computed_merkle_root, block.merkle_root);
+}
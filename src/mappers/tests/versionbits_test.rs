@@ -0,0 +1,139 @@
+use crate::mappers::versionbits::{
+    decide_outcome, deployment_period, has_started, signaled_bits, signals, state_at, DeploymentState,
+};
+use crate::pb::bitcoin::analytics::v1::DeploymentOutcome;
+use crate::utils::deployments::{ActivationMode, Deployment, Deployments};
+
+fn taproot() -> Deployment {
+    Deployments::load("")
+        .unwrap()
+        .deployments
+        .into_iter()
+        .find(|deployment| deployment.name == "taproot")
+        .unwrap()
+}
+
+/// Replay the period boundaries of a deployment the way the stores do, returning the state of each period
+fn replay(deployment: &Deployment, periods: &[(u64, i64, i64)]) -> Vec<DeploymentState> {
+    let mut started = None;
+    let mut outcome: Option<DeploymentOutcome> = None;
+    let mut counts = std::collections::HashMap::new();
+    for &(period, median_time, signal_count) in periods {
+        let height = period * deployment.period;
+        if started.is_none() && has_started(deployment, height, median_time) {
+            started = Some(period);
+        }
+        let previous_count = counts.get(&(period - 1)).copied().unwrap_or(0);
+        if outcome.is_none() {
+            outcome = decide_outcome(deployment, period, started, previous_count, median_time);
+        }
+        counts.insert(period, signal_count);
+    }
+    periods
+        .iter()
+        .map(|&(period, _, _)| state_at(deployment, period, started, outcome.as_ref()))
+        .collect()
+}
+
+#[test]
+fn test_signaled_bits() {
+    assert_eq!(signaled_bits(0x2000_0004), 0x4);
+    assert_eq!(signaled_bits(0x2000_0000), 0);
+    // Version rolling (BIP320) uses bits 13 to 28
+    assert_eq!(signaled_bits(0x3fff_e004), 0x1fff_e004);
+    // Versions from before BIP9 or with other top bits don't signal
+    assert_eq!(signaled_bits(2), 0);
+    assert_eq!(signaled_bits(0x6000_0004), 0);
+    assert_eq!(signaled_bits(-0x2000_0000), 0);
+
+    let taproot = taproot();
+    assert!(signals(0x2000_0004, &taproot));
+    assert!(!signals(0x2000_0002, &taproot));
+}
+
+#[test]
+fn test_taproot_activation_replay() {
+    let deployment = taproot();
+    // Median time past at each period boundary, crossing the start at period 338 (block 681408)
+    // and the timeout at period 346, after the deployment locked in
+    let periods: Vec<(u64, i64, i64)> = (337..=353)
+        .map(|period| {
+            let median_time = deployment.start + (period as i64 - 338) * 1_200_000 + 100;
+            let signal_count = match period {
+                338 => 1_000,
+                339 => 1_500,
+                340 => 1_900,
+                _ => 2_000,
+            };
+            (period, median_time, signal_count)
+        })
+        .collect();
+
+    let states = replay(&deployment, &periods);
+    use DeploymentState::*;
+    assert_eq!(
+        states,
+        vec![
+            Defined, Started, Started, Started, LockedIn, LockedIn, LockedIn, LockedIn, LockedIn, LockedIn,
+            LockedIn, LockedIn, LockedIn, LockedIn, LockedIn, Active, Active,
+        ]
+    );
+    // Active from block 709632, its min_activation_height
+    assert_eq!(352 * deployment.period, 709_632);
+}
+
+#[test]
+fn test_deployment_fails_on_timeout() {
+    let deployment = taproot();
+    let periods: Vec<(u64, i64, i64)> = (338..=348)
+        .map(|period| (period, deployment.start + (period as i64 - 338) * 1_200_000 + 100, 1_814))
+        .collect();
+
+    let states = replay(&deployment, &periods);
+    assert_eq!(states[7], DeploymentState::Started);
+    assert_eq!(states[8], DeploymentState::Failed);
+    assert_eq!(states[10], DeploymentState::Failed);
+}
+
+#[test]
+fn test_bip8_lock_in_on_timeout() {
+    let deployment = Deployment {
+        name: "lot".to_string(),
+        bit: 5,
+        start: 2016 * 10,
+        timeout: 2016 * 13,
+        threshold: 1815,
+        period: 2016,
+        min_activation_height: 0,
+        mode: ActivationMode::Bip8,
+        lock_in_on_timeout: true,
+    };
+    let periods: Vec<(u64, i64, i64)> = (9..=15).map(|period| (period, 0, 0)).collect();
+
+    use DeploymentState::*;
+    assert_eq!(replay(&deployment, &periods), vec![Defined, Started, Started, Started, LockedIn, Active, Active]);
+}
+
+#[test]
+fn test_deployment_period() {
+    let deployment = taproot();
+    let outcome = DeploymentOutcome {
+        state: "LOCKED_IN".to_string(),
+        period: 341,
+    };
+
+    let record = deployment_period(&deployment, 340, 1_900, Some(338), Some(&outcome));
+    assert_eq!(record.deployment, "taproot");
+    assert_eq!(record.bit, 2);
+    assert_eq!(record.start_height, 685_440);
+    assert_eq!(record.end_height, 687_455);
+    assert_eq!(record.signal_count, 1_900);
+    assert_eq!(record.threshold, 1_815);
+    assert_eq!(record.state, "STARTED");
+    assert_eq!(record.next_state, "LOCKED_IN");
+    assert!(record.transition);
+
+    let record = deployment_period(&deployment, 345, 2_000, Some(338), Some(&outcome));
+    assert_eq!(record.state, "LOCKED_IN");
+    assert!(!record.transition);
+}
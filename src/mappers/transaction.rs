@@ -0,0 +1,93 @@
+use crate::mappers::spend;
+use crate::mappers::utxo::{self, UtxoLookup};
+use crate::pb::bitcoin::analytics::v1::{TransactionMetrics, TransactionMetricsRecord};
+use crate::utils;
+use crate::utils::ids::{Txid, Wtxid};
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, Transaction};
+
+/// Extract per-transaction metrics, resolving input values from the UTXO store
+///
+/// The input value and fee of a transaction with an unresolved input are left at 0 and
+/// flagged by `inputs_resolved`, rather than understating them.
+pub fn extract_transaction_metrics<S: UtxoLookup>(
+    block: &Block,
+    utxo_store: &S,
+) -> Result<Vec<TransactionMetricsRecord>, Error> {
+    let mut records = Vec::with_capacity(block.tx.len());
+
+    for (tx_idx, tx) in block.tx.iter().enumerate() {
+        let is_coinbase = utils::bitcoin_utils::is_coinbase_transaction(tx);
+        let prevouts = utxo::resolve_prevouts(tx, tx_idx, utxo_store);
+
+        let inputs_resolved = is_coinbase || prevouts.iter().all(Option::is_some);
+        let input_value: u64 = if inputs_resolved {
+            prevouts.iter().flatten().map(|prevout| prevout.value).sum()
+        } else {
+            0
+        };
+        let output_value = utils::bitcoin_utils::transaction_output_value(tx)?.to_sat();
+        let fee = utxo::transaction_fee(tx, &prevouts).unwrap_or(0);
+
+        let vsize = transaction_vsize(tx);
+        let fee_rate = if vsize > 0 { fee as f64 / vsize as f64 } else { 0.0 };
+
+        let metrics = TransactionMetrics {
+            hash: Txid::from_hex(&tx.txid)?.to_string(),
+            block_number: block.height as u64,
+            block_timestamp: block.time as u64,
+            input_count: tx.vin.len() as u32,
+            output_count: tx.vout.len() as u32,
+            fee,
+            fee_rate,
+            size: tx.size as u32,
+            weight: tx.weight as u32,
+            is_coinbase,
+            version: tx.version,
+            has_witness: utils::is_segwit_transaction(tx),
+            locktime: tx.locktime,
+            input_value,
+            output_value,
+            tx_type: transaction_type(tx, is_coinbase)?.to_string(),
+            wtxid: Wtxid::from_hex(&tx.hash)?.to_string(),
+            inputs_resolved,
+        };
+
+        records.push(TransactionMetricsRecord {
+            table: "bitcoin_transactions".to_string(),
+            transaction: Some(metrics),
+        });
+    }
+
+    Ok(records)
+}
+
+/// Virtual size in vbytes, derived from the weight when Firehose doesn't provide it
+pub fn transaction_vsize(tx: &Transaction) -> u64 {
+    if tx.vsize > 0 {
+        return tx.vsize as u64;
+    }
+    (tx.weight.max(0) as u64).div_ceil(4)
+}
+
+/// Classify a transaction by the most recent protocol feature it uses
+///
+/// A transaction is `TAPROOT` when it spends a key-path or script-path input or creates a P2TR
+/// output, inputs being classified like the `spend_type` of spent UTXOs.
+fn transaction_type(tx: &Transaction, is_coinbase: bool) -> Result<&'static str, Error> {
+    if is_coinbase {
+        return Ok("COINBASE");
+    }
+    for vin in &tx.vin {
+        if spend::classify_input(vin)?.is_taproot() {
+            return Ok("TAPROOT");
+        }
+    }
+    Ok(if utils::is_taproot_transaction(tx) {
+        "TAPROOT"
+    } else if utils::is_segwit_transaction(tx) {
+        "SEGWIT"
+    } else {
+        "LEGACY"
+    })
+}
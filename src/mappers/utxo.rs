@@ -1,43 +1,161 @@
+use crate::mappers::spend;
 use crate::pb::bitcoin::utxo::v1::{UTXO, UTXORecord};
 use crate::utils;
+use crate::utils::amount::Amount;
+use crate::utils::ids::Txid;
+use crate::utils::script::ScriptType;
 use substreams::errors::Error;
+use substreams::store::{StoreDelete, StoreGet, StoreGetProto, StoreSet, StoreSetProto};
 use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{Block, Transaction};
 use std::collections::HashMap;
 
-/// Process a block to track UTXOs
-pub fn process_utxos(block: &Block) -> Result<Vec<UTXORecord>, Error> {
+/// Script type of spent outputs that couldn't be resolved from the UTXO store
+pub const UNRESOLVED_SCRIPT_TYPE: &str = "UNKNOWN";
+
+/// Build the `txid:vout` key of an output in the UTXO store.
+///
+/// The output index is zero-padded so that deleting a spent key by prefix
+/// can never match a sibling output (`txid:1` would otherwise match `txid:10`).
+pub fn utxo_key(tx_id: &str, vout_index: u32) -> String {
+    format!("{}:{:010}", tx_id, vout_index)
+}
+
+/// Store ordinal at which the outputs of the transaction at `tx_index` are created
+pub fn output_ordinal(tx_index: usize) -> u64 {
+    tx_index as u64 * 2
+}
+
+/// Store ordinal at which the outputs spent by the transaction at `tx_index` are removed
+pub fn spend_ordinal(tx_index: usize) -> u64 {
+    tx_index as u64 * 2 + 1
+}
+
+/// Resolves previously created outputs by their `txid:vout` key
+pub trait UtxoLookup {
+    /// Return the output stored under `key` as seen at `ordinal` within the current block
+    fn lookup(&self, ordinal: u64, key: &str) -> Option<UTXO>;
+}
+
+impl UtxoLookup for StoreGetProto<UTXO> {
+    fn lookup(&self, ordinal: u64, key: &str) -> Option<UTXO> {
+        self.get_at(ordinal, key)
+    }
+}
+
+impl UtxoLookup for HashMap<String, UTXO> {
+    fn lookup(&self, _ordinal: u64, key: &str) -> Option<UTXO> {
+        self.get(key).cloned()
+    }
+}
+
+/// Resolve the outputs spent by the transaction at `tx_index`, one entry per input
+///
+/// Entries are `None` for the coinbase input and for outputs missing from the store.
+pub fn resolve_prevouts<S: UtxoLookup>(tx: &Transaction, tx_index: usize, store: &S) -> Vec<Option<UTXO>> {
+    tx.vin
+        .iter()
+        .map(|vin| {
+            // The coinbase input has no txid
+            let prev_tx_id = Txid::from_hex(&vin.txid).ok()?;
+            store.lookup(output_ordinal(tx_index), &utxo_key(&prev_tx_id.to_string(), vin.vout))
+        })
+        .collect()
+}
+
+/// Compute the fee of a non-coinbase transaction as its input value minus its output value
+///
+/// Returns `None` for the coinbase transaction or when any spent output is unresolved.
+pub fn transaction_fee(tx: &Transaction, prevouts: &[Option<UTXO>]) -> Option<u64> {
+    if utils::bitcoin_utils::is_coinbase_transaction(tx) || prevouts.is_empty() {
+        return None;
+    }
+
+    let mut input_value = Amount::ZERO;
+    for prevout in prevouts {
+        input_value = input_value.checked_add(Amount::from_sat(prevout.as_ref()?.value))?;
+    }
+    let output_value = utils::bitcoin_utils::transaction_output_value(tx).ok()?;
+
+    Some(input_value.saturating_sub(output_value).to_sat())
+}
+
+/// Apply a block to the UTXO store: add every spendable output, remove every spent one
+pub fn update_utxo_store(block: &Block, store: &StoreSetProto<UTXO>) -> Result<(), Error> {
+    let block_height = block.height as u64;
+    let block_time = block.time as u64;
+
+    for (tx_idx, tx) in block.tx.iter().enumerate() {
+        for utxo in extract_transaction_utxos(tx, block_height, block_time)? {
+            // Unspendable outputs, including non-standard OP_RETURN scripts, never need resolving
+            let unspendable = tx.vout[utxo.vout_index as usize]
+                .script_pub_key
+                .as_ref()
+                .and_then(|script| hex::decode(&script.hex).ok())
+                .is_some_and(|script| utils::script::is_unspendable(&script));
+            if unspendable {
+                continue;
+            }
+            store.set(output_ordinal(tx_idx), utxo_key(&utxo.tx_id, utxo.vout_index), &utxo);
+        }
+
+        for vin in &tx.vin {
+            if vin.txid.is_empty() {
+                continue;
+            }
+            let key = utxo_key(&Txid::from_hex(&vin.txid)?.to_string(), vin.vout);
+            store.delete_prefix(spend_ordinal(tx_idx) as i64, &key);
+        }
+    }
+
+    Ok(())
+}
+
+/// Process a block to track UTXOs, resolving spent outputs from the UTXO store
+pub fn process_utxos<S: UtxoLookup>(block: &Block, store: &S) -> Result<Vec<UTXORecord>, Error> {
     let mut utxo_records = Vec::new();
     let block_height = block.height as u64;
     let block_time = block.time as u64;
     
     // Process each transaction in the block
-    for tx in &block.tx {
-        let tx_id = utils::to_hex_string(&tx.hash);
+    for (tx_idx, tx) in block.tx.iter().enumerate() {
+        // Outputs are referenced by txid: the wtxid (`tx.hash`) differs for witness transactions
+        let tx_id = Txid::from_hex(&tx.txid)?.to_string();
         
         // Process inputs (mark UTXOs as spent)
         for (vin_idx, vin) in tx.vin.iter().enumerate() {
             // Skip coinbase inputs (first input of first transaction in a block)
-            if vin_idx == 0 && tx.vin.len() > 0 && tx.vin[0].txid.is_empty() {
+            if vin_idx == 0 && !tx.vin.is_empty() && tx.vin[0].txid.is_empty() {
                 continue;
             }
             
             // Check if the txid is not empty
             if !vin.txid.is_empty() {
-                let prev_tx_id_hex = utils::to_hex_string(&vin.txid);
-                let vout_index = vin.vout as u32;
-                
-                // Create a spent UTXO record
-                // In a real implementation, we would look up the actual UTXO data
-                // For this example, we'll create a placeholder with minimal data
-                let spent_utxo = UTXO {
-                    tx_id: prev_tx_id_hex.clone(),
-                    vout_index,
-                    value: 0, // In a real implementation, this would be the actual value
-                    script_type: "UNKNOWN".to_string(),
-                    address: "".to_string(), // In a real implementation, this would be the actual address
-                    block_height: 0, // In a real implementation, this would be the block height when created
-                    block_time: 0,   // In a real implementation, this would be the block time when created
+                let prev_tx_id_hex = Txid::from_hex(&vin.txid)?.to_string();
+                let vout_index = vin.vout;
+                let key = utxo_key(&prev_tx_id_hex, vout_index);
+
+                // Read the store before this transaction's spends are removed from it
+                let mut spent_utxo = match store.lookup(output_ordinal(tx_idx), &key) {
+                    Some(utxo) => utxo,
+                    None => {
+                        // Only happens when the store did not start at genesis
+                        substreams::log::info!("UTXO {} not found in store", key);
+                        UTXO {
+                            tx_id: prev_tx_id_hex,
+                            vout_index,
+                            script_type: UNRESOLVED_SCRIPT_TYPE.to_string(),
+                            ..Default::default()
+                        }
+                    }
                 };
+
+                // Link the output to the input spending it
+                spent_utxo.is_spent = true;
+                spent_utxo.spent_in_tx_id = tx_id.clone();
+                spent_utxo.spent_in_input_index = vin_idx as u32;
+                spent_utxo.spent_at_block_height = block_height;
+                spent_utxo.spent_at_block_time = block_time;
+                spent_utxo.spend_type = spend::classify_input(vin)?.name().to_string();
                 
                 // Create a record to mark this UTXO as spent
                 let spent_record = UTXORecord {
@@ -53,7 +171,8 @@ pub fn process_utxos(block: &Block) -> Result<Vec<UTXORecord>, Error> {
         for (vout_idx, vout) in tx.vout.iter().enumerate() {
             if let Some(script) = &vout.script_pub_key {
                 let script_bytes = hex::decode(&script.hex).unwrap_or_default();
-                let script_type = utils::bitcoin_utils::parse_output_script(&script_bytes);
+                let script_type = utils::script::classify(&script_bytes);
+                let (multisig_required, multisig_total) = multisig_counts(script_type);
                 let address = utils::bitcoin_utils::extract_address_from_script(&script_bytes, false)
                     .unwrap_or_default();
                 
@@ -61,11 +180,14 @@ pub fn process_utxos(block: &Block) -> Result<Vec<UTXORecord>, Error> {
                 let utxo = UTXO {
                     tx_id: tx_id.clone(),
                     vout_index: vout_idx as u32,
-                    value: vout.value as u64,
-                    script_type,
+                    value: Amount::from_btc(vout.value)?.to_sat(),
+                    script_type: script_type.name(),
+                    multisig_required,
+                    multisig_total,
                     address,
                     block_height,
                     block_time,
+                    ..Default::default()
                 };
                 
                 // Create a record for this new UTXO
@@ -82,19 +204,31 @@ pub fn process_utxos(block: &Block) -> Result<Vec<UTXORecord>, Error> {
     Ok(utxo_records)
 }
 
+/// Signatures required and keys of a bare multisig script, zeros for other scripts
+fn multisig_counts(script_type: ScriptType) -> (u32, u32) {
+    match script_type {
+        ScriptType::Multisig { required, total } => (required as u32, total as u32),
+        _ => (0, 0),
+    }
+}
+
 /// Calculate token balances from UTXOs
-pub fn calculate_token_balances(utxos: &[UTXO]) -> HashMap<String, (u64, u32)> {
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn calculate_token_balances(utxos: &[UTXO]) -> Result<HashMap<String, (Amount, u32)>, Error> {
     let mut balances = HashMap::new();
     
     for utxo in utxos {
         if !utxo.address.is_empty() {
-            let entry = balances.entry(utxo.address.clone()).or_insert((0, 0));
-            entry.0 += utxo.value;
+            let entry = balances.entry(utxo.address.clone()).or_insert((Amount::ZERO, 0));
+            entry.0 = entry
+                .0
+                .checked_add(Amount::from_sat(utxo.value))
+                .ok_or_else(|| Error::msg(format!("Balance of {} exceeds the supply cap", utxo.address)))?;
             entry.1 += 1;
         }
     }
     
-    balances
+    Ok(balances)
 }
 
 /// Extract UTXOs from a transaction
@@ -102,15 +236,16 @@ pub fn extract_transaction_utxos(
     tx: &Transaction,
     block_height: u64,
     block_time: u64,
-) -> Vec<UTXO> {
+) -> Result<Vec<UTXO>, Error> {
     let mut utxos = Vec::new();
-    let tx_id = utils::to_hex_string(&tx.hash);
+    let tx_id = Txid::from_hex(&tx.txid)?.to_string();
     
     // Process outputs to create new UTXOs
     for (vout_idx, vout) in tx.vout.iter().enumerate() {
         if let Some(script) = &vout.script_pub_key {
             let script_bytes = hex::decode(&script.hex).unwrap_or_default();
-            let script_type = utils::bitcoin_utils::parse_output_script(&script_bytes);
+            let script_type = utils::script::classify(&script_bytes);
+            let (multisig_required, multisig_total) = multisig_counts(script_type);
             let address = utils::bitcoin_utils::extract_address_from_script(&script_bytes, false)
                 .unwrap_or_default();
             
@@ -118,34 +253,34 @@ pub fn extract_transaction_utxos(
             let utxo = UTXO {
                 tx_id: tx_id.clone(),
                 vout_index: vout_idx as u32,
-                value: vout.value as u64,
-                script_type,
+                value: Amount::from_btc(vout.value)?.to_sat(),
+                script_type: script_type.name(),
+                multisig_required,
+                multisig_total,
                 address,
                 block_height,
                 block_time,
+                ..Default::default()
             };
             
             utxos.push(utxo);
         }
     }
     
-    utxos
+    Ok(utxos)
 }
 
 /// Check if a transaction input spends a specific UTXO
+#[cfg_attr(not(test), allow(dead_code))]
 pub fn is_utxo_spent(
     tx: &Transaction,
     utxo_tx_id: &str,
     utxo_vout_index: u32,
 ) -> bool {
-    for vin in &tx.vin {
-        if !vin.txid.is_empty() {
-            let prev_tx_id_hex = utils::to_hex_string(&vin.txid);
-            if prev_tx_id_hex == utxo_tx_id && vin.vout as u32 == utxo_vout_index {
-                return true;
-            }
-        }
-    }
-    
-    false
+    let Ok(utxo_tx_id) = Txid::from_hex(utxo_tx_id) else {
+        return false;
+    };
+    tx.vin
+        .iter()
+        .any(|vin| vin.vout == utxo_vout_index && Txid::from_hex(&vin.txid).is_ok_and(|txid| txid == utxo_tx_id))
 }
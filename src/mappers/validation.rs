@@ -0,0 +1,146 @@
+use crate::mappers::coinbase;
+use crate::pb::bitcoin::analytics::v1::BlockValidation;
+use crate::utils::bitcoin_utils;
+use crate::utils::compact;
+use crate::utils::hashes;
+use crate::utils::header;
+use crate::utils::ids::{BlockHash, TxMerkleRoot, Txid, Wtxid};
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+/// A disagreement between a block and the hashes recomputed from its transactions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+    /// A header field can't be serialized, e.g. a malformed hash or bits
+    InvalidHeader,
+    /// The hash of the serialized header differs from the block hash
+    BlockHash,
+    /// The block hash exceeds the target encoded in bits
+    ProofOfWork,
+    /// A txid or wtxid isn't a 32-byte hex hash
+    InvalidTransactionId,
+    /// The merkle root of the txids differs from the header
+    MerkleRoot,
+    /// The merkle tree pairs identical nodes, so other transactions could share its root
+    MutatedMerkleTree,
+    /// The first transaction isn't a coinbase
+    MissingCoinbase,
+    /// Transactions carry witness data but the coinbase commits to none
+    MissingWitnessCommitment,
+    /// The coinbase commits to witnesses without a 32-byte witness reserved value
+    MissingWitnessReservedValue,
+    /// The witness commitment of the coinbase differs from the recomputed one
+    WitnessCommitment,
+}
+
+impl Mismatch {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mismatch::InvalidHeader => "INVALID_HEADER",
+            Mismatch::BlockHash => "BLOCK_HASH",
+            Mismatch::ProofOfWork => "PROOF_OF_WORK",
+            Mismatch::InvalidTransactionId => "INVALID_TRANSACTION_ID",
+            Mismatch::MerkleRoot => "MERKLE_ROOT",
+            Mismatch::MutatedMerkleTree => "MUTATED_MERKLE_TREE",
+            Mismatch::MissingCoinbase => "MISSING_COINBASE",
+            Mismatch::MissingWitnessCommitment => "MISSING_WITNESS_COMMITMENT",
+            Mismatch::MissingWitnessReservedValue => "MISSING_WITNESS_RESERVED_VALUE",
+            Mismatch::WitnessCommitment => "WITNESS_COMMITMENT",
+        }
+    }
+}
+
+/// Recompute the header hash, merkle root and witness commitment of a block and compare them
+/// with the block, checking the proof of work against the target
+pub fn validate_block(block: &Block) -> BlockValidation {
+    let mut mismatches = Vec::new();
+
+    let mut computed_block_hash = String::new();
+    match header::header_hash(block) {
+        Ok(hash) => {
+            if BlockHash::from_hex(&block.hash).ok() != Some(hash) {
+                mismatches.push(Mismatch::BlockHash);
+            }
+            // Bits were parsed when serializing the header
+            let bits = compact::parse_bits(&block.bits).unwrap_or_default();
+            if !header::check_proof_of_work(hash, bits).unwrap_or(false) {
+                mismatches.push(Mismatch::ProofOfWork);
+            }
+            computed_block_hash = hash.to_string();
+        }
+        Err(_) => mismatches.push(Mismatch::InvalidHeader),
+    }
+
+    let txids: Option<Vec<[u8; 32]>> = block
+        .tx
+        .iter()
+        .map(|tx| Txid::from_hex(&tx.txid).ok().map(Txid::to_byte_array))
+        .collect();
+    // The coinbase is committed with a wtxid of zero
+    let wtxids: Option<Vec<[u8; 32]>> = block
+        .tx
+        .iter()
+        .enumerate()
+        .map(|(i, tx)| match i {
+            0 => Some([0u8; 32]),
+            _ => Wtxid::from_hex(&tx.hash).ok().map(Wtxid::to_byte_array),
+        })
+        .collect();
+    if txids.is_none() || wtxids.is_none() {
+        mismatches.push(Mismatch::InvalidTransactionId);
+    }
+
+    let mut computed_merkle_root = String::new();
+    if let Some(txids) = &txids {
+        let (root, mutated) = hashes::merkle_root(txids);
+        let root = TxMerkleRoot::from_byte_array(root);
+        if TxMerkleRoot::from_hex(&block.merkle_root).ok() != Some(root) {
+            mismatches.push(Mismatch::MerkleRoot);
+        }
+        if mutated {
+            mismatches.push(Mismatch::MutatedMerkleTree);
+        }
+        computed_merkle_root = root.to_string();
+    }
+
+    let coinbase_tx = block.tx.first().filter(|tx| bitcoin_utils::is_coinbase_transaction(tx));
+    if coinbase_tx.is_none() {
+        mismatches.push(Mismatch::MissingCoinbase);
+    }
+    let commitment = coinbase_tx.and_then(coinbase::witness_commitment);
+    let has_witness = block.tx.iter().skip(1).any(bitcoin_utils::is_segwit_transaction);
+
+    let mut computed_witness_commitment = String::new();
+    match (&commitment, &wtxids) {
+        (None, _) if has_witness => mismatches.push(Mismatch::MissingWitnessCommitment),
+        (Some(commitment), Some(wtxids)) => {
+            let reserved_value = coinbase_tx
+                .and_then(|tx| tx.vin[0].txinwitness.first())
+                .and_then(|item| hex::decode(item).ok())
+                .filter(|item| item.len() == 32);
+            match reserved_value {
+                Some(reserved_value) => {
+                    let (witness_root, _) = hashes::merkle_root(wtxids);
+                    let computed = hashes::sha256d(&[witness_root.as_slice(), &reserved_value].concat());
+                    if computed.as_slice() != commitment.as_slice() {
+                        mismatches.push(Mismatch::WitnessCommitment);
+                    }
+                    computed_witness_commitment = hex::encode(computed);
+                }
+                None => mismatches.push(Mismatch::MissingWitnessReservedValue),
+            }
+        }
+        _ => {}
+    }
+
+    BlockValidation {
+        block_height: block.height as u64,
+        block_hash: block.hash.clone(),
+        computed_block_hash,
+        valid: mismatches.is_empty(),
+        merkle_root: block.merkle_root.clone(),
+        computed_merkle_root,
+        witness_commitment: commitment.map(hex::encode).unwrap_or_default(),
+        computed_witness_commitment,
+        mismatches: mismatches.iter().map(|mismatch| mismatch.as_str().to_string()).collect(),
+    }
+}
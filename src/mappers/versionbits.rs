@@ -0,0 +1,176 @@
+use crate::pb::bitcoin::analytics::v1::{BlockTimestamp, DeploymentOutcome, DeploymentPeriod};
+use crate::utils::deployments::{ActivationMode, Deployment};
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+/// Top 3 bits of a version that signals through version bits (BIP9)
+const VERSIONBITS_TOP_MASK: u32 = 0xe000_0000;
+const VERSIONBITS_TOP_BITS: u32 = 0x2000_0000;
+
+/// Bits available for signaling
+const VERSIONBITS_MASK: u32 = 0x1fff_ffff;
+
+/// State of a deployment during a signaling period
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeploymentState {
+    Defined,
+    Started,
+    LockedIn,
+    Active,
+    Failed,
+}
+
+impl DeploymentState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeploymentState::Defined => "DEFINED",
+            DeploymentState::Started => "STARTED",
+            DeploymentState::LockedIn => "LOCKED_IN",
+            DeploymentState::Active => "ACTIVE",
+            DeploymentState::Failed => "FAILED",
+        }
+    }
+}
+
+/// Bits signaled by a block version, 0 when the version doesn't use version bits
+pub fn signaled_bits(version: i32) -> u32 {
+    let version = version as u32;
+    if version & VERSIONBITS_TOP_MASK != VERSIONBITS_TOP_BITS {
+        return 0;
+    }
+    version & VERSIONBITS_MASK
+}
+
+/// Whether a block version signals for `deployment`
+pub fn signals(version: i32, deployment: &Deployment) -> bool {
+    signaled_bits(version) & (1 << deployment.bit) != 0
+}
+
+/// Median time past of the parent block, 0 when `previous` isn't the parent
+pub fn parent_median_time(block: &Block, previous: Option<&BlockTimestamp>) -> i64 {
+    previous
+        .filter(|previous| previous.number + 1 == block.height as u64)
+        .map_or(0, |previous| previous.median_time)
+}
+
+/// Signaling period of a block height
+pub fn period_of(height: u64, deployment: &Deployment) -> u64 {
+    height / deployment.period
+}
+
+/// Whether `height` is the first block of a signaling period, where states change
+pub fn is_period_start(height: u64, deployment: &Deployment) -> bool {
+    height.is_multiple_of(deployment.period)
+}
+
+/// Prefix shared by the aggregates of a deployment period
+pub fn period_prefix(deployment: &Deployment, period: u64) -> String {
+    format!("{}:{}:", deployment.name, period)
+}
+
+/// Key of the signaling count of a deployment period in `store_deployment_signals`
+pub fn signal_key(deployment: &Deployment, period: u64) -> String {
+    format!("{}signals", period_prefix(deployment, period))
+}
+
+/// Whether the start of a deployment is reached at the first block of a period
+///
+/// `parent_median_time` is the median time past of the last block of the previous period.
+/// Median time past never decreases, so this needs no knowledge of the previous state.
+pub fn has_started(deployment: &Deployment, height: u64, parent_median_time: i64) -> bool {
+    match deployment.mode {
+        ActivationMode::Bip9 => parent_median_time >= deployment.start,
+        ActivationMode::Bip8 => height as i64 >= deployment.start,
+    }
+}
+
+/// Whether the timeout of a deployment is reached at the first block of a period
+pub fn has_timed_out(deployment: &Deployment, height: u64, parent_median_time: i64) -> bool {
+    match deployment.mode {
+        ActivationMode::Bip9 => parent_median_time >= deployment.timeout,
+        ActivationMode::Bip8 => height as i64 >= deployment.timeout,
+    }
+}
+
+/// Outcome decided at the first block of `period`, if the deployment was started before it
+///
+/// The threshold is checked before the timeout, as Bitcoin Core does. Outcomes are kept in a
+/// `set_if_not_exists` store, so only the first one decided sticks. The mandatory signaling
+/// period of BIP8 with `lock_in_on_timeout` is reported as STARTED.
+pub fn decide_outcome(
+    deployment: &Deployment,
+    period: u64,
+    started_period: Option<u64>,
+    signal_count: i64,
+    parent_median_time: i64,
+) -> Option<DeploymentOutcome> {
+    if started_period.is_none_or(|started| started >= period) {
+        return None;
+    }
+
+    let height = period * deployment.period;
+    let state = if signal_count >= deployment.threshold as i64 {
+        DeploymentState::LockedIn
+    } else if has_timed_out(deployment, height, parent_median_time) {
+        if deployment.mode == ActivationMode::Bip8 && deployment.lock_in_on_timeout {
+            DeploymentState::LockedIn
+        } else {
+            DeploymentState::Failed
+        }
+    } else {
+        return None;
+    };
+
+    Some(DeploymentOutcome {
+        state: state.as_str().to_string(),
+        period,
+    })
+}
+
+/// State of a deployment during `period`, from the period it started in and its outcome
+///
+/// A locked in deployment becomes active one period later, once `min_activation_height` is reached.
+pub fn state_at(
+    deployment: &Deployment,
+    period: u64,
+    started_period: Option<u64>,
+    outcome: Option<&DeploymentOutcome>,
+) -> DeploymentState {
+    if let Some(outcome) = outcome.filter(|outcome| outcome.period <= period) {
+        if outcome.state == DeploymentState::Failed.as_str() {
+            return DeploymentState::Failed;
+        }
+        if period > outcome.period && period * deployment.period >= deployment.min_activation_height {
+            return DeploymentState::Active;
+        }
+        return DeploymentState::LockedIn;
+    }
+    if started_period.is_some_and(|started| started <= period) {
+        return DeploymentState::Started;
+    }
+    DeploymentState::Defined
+}
+
+/// Build the signaling summary of a completed period, emitted at the first block of the next one
+pub fn deployment_period(
+    deployment: &Deployment,
+    period: u64,
+    signal_count: i64,
+    started_period: Option<u64>,
+    outcome: Option<&DeploymentOutcome>,
+) -> DeploymentPeriod {
+    let state = state_at(deployment, period, started_period, outcome);
+    let next_state = state_at(deployment, period + 1, started_period, outcome);
+    DeploymentPeriod {
+        deployment: deployment.name.clone(),
+        bit: deployment.bit as u32,
+        period,
+        start_height: period * deployment.period,
+        end_height: (period + 1) * deployment.period - 1,
+        signal_count: signal_count as u32,
+        threshold: deployment.threshold,
+        period_length: deployment.period as u32,
+        state: state.as_str().to_string(),
+        next_state: next_state.as_str().to_string(),
+        transition: state != next_state,
+    }
+}
@@ -1,3 +1,5 @@
+// Not every generated message is used by the modules, and prost keeps the proto names
+#[allow(dead_code, clippy::upper_case_acronyms)]
 pub mod bitcoin {
     pub mod utxo {
         pub mod v1 {
@@ -21,6 +23,22 @@ pub mod bitcoin {
                 pub block_height: u64,
                 #[prost(uint64, tag="7")]
                 pub block_time: u64,
+                #[prost(bool, tag="8")]
+                pub is_spent: bool,
+                #[prost(string, tag="9")]
+                pub spent_in_tx_id: String,
+                #[prost(uint32, tag="10")]
+                pub spent_in_input_index: u32,
+                #[prost(uint64, tag="11")]
+                pub spent_at_block_height: u64,
+                #[prost(uint64, tag="12")]
+                pub spent_at_block_time: u64,
+                #[prost(uint32, tag="13")]
+                pub multisig_required: u32,
+                #[prost(uint32, tag="14")]
+                pub multisig_total: u32,
+                #[prost(string, tag="15")]
+                pub spend_type: ::prost::alloc::string::String,
             }
             
             #[derive(Clone, PartialEq, ::prost::Message)]
@@ -105,6 +123,62 @@ pub mod bitcoin {
                 pub nonce: u32,
                 #[prost(uint32, tag="14")]
                 pub bits: u32,
+                #[prost(uint64, tag="15")]
+                pub coinbase_fees: u64,
+                #[prost(int64, tag="16")]
+                pub block_interval: i64,
+                #[prost(uint64, tag="17")]
+                pub median_time: u64,
+                #[prost(string, tag="18")]
+                pub miner_match_method: String,
+                #[prost(string, tag="19")]
+                pub target: String,
+                #[prost(string, tag="20")]
+                pub work: String,
+                #[prost(string, tag="21")]
+                pub chainwork: String,
+                #[prost(uint32, tag="22")]
+                pub version_bits: u32,
+                #[prost(double, tag="23")]
+                pub segwit_percent: f64,
+                #[prost(double, tag="24")]
+                pub taproot_percent: f64,
+                #[prost(uint32, tag="25")]
+                pub segwit_tx_count: u32,
+                #[prost(uint32, tag="26")]
+                pub nested_segwit_tx_count: u32,
+                #[prost(uint32, tag="27")]
+                pub taproot_tx_count: u32,
+                #[prost(uint32, tag="28")]
+                pub input_count: u32,
+                #[prost(uint32, tag="29")]
+                pub segwit_input_count: u32,
+                #[prost(uint32, tag="30")]
+                pub nested_segwit_input_count: u32,
+                #[prost(uint32, tag="31")]
+                pub taproot_input_count: u32,
+                #[prost(uint32, tag="32")]
+                pub output_count: u32,
+                #[prost(uint32, tag="33")]
+                pub segwit_output_count: u32,
+                #[prost(uint32, tag="34")]
+                pub taproot_output_count: u32,
+                #[prost(uint64, tag="35")]
+                pub witness_bytes: u64,
+                #[prost(uint64, tag="36")]
+                pub witness_weight_saved: u64,
+                #[prost(bool, tag="37")]
+                pub fees_resolved: bool,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct BlockTimestamp {
+                #[prost(uint64, tag="1")]
+                pub number: u64,
+                #[prost(int64, tag="2")]
+                pub time: i64,
+                #[prost(int64, tag="3")]
+                pub median_time: i64,
             }
             
             #[derive(Clone, PartialEq, ::prost::Message)]
@@ -141,6 +215,10 @@ pub mod bitcoin {
                 pub output_value: u64,
                 #[prost(string, tag="16")]
                 pub tx_type: String,
+                #[prost(string, tag="17")]
+                pub wtxid: String,
+                #[prost(bool, tag="18")]
+                pub inputs_resolved: bool,
             }
             
             #[derive(Clone, PartialEq, ::prost::Message)]
@@ -163,6 +241,8 @@ pub mod bitcoin {
                 pub address_tag: String,
                 #[prost(string, tag="9")]
                 pub address_category: String,
+                #[prost(uint32, tag="10")]
+                pub io_index: u32,
             }
             
             #[derive(Clone, PartialEq, ::prost::Message)]
@@ -193,18 +273,6 @@ pub mod bitcoin {
                 pub active_addresses: u32,
             }
             
-            #[derive(Clone, PartialEq, ::prost::Message)]
-            pub struct DatabaseChanges {
-                #[prost(message, repeated, tag="1")]
-                pub block_metrics: Vec<BlockMetricsRecord>,
-                #[prost(message, repeated, tag="2")]
-                pub transaction_metrics: Vec<TransactionMetricsRecord>,
-                #[prost(message, repeated, tag="3")]
-                pub address_activities: Vec<AddressActivityRecord>,
-                #[prost(message, repeated, tag="4")]
-                pub daily_metrics: Vec<NetworkDailyMetricsRecord>,
-            }
-            
             #[derive(Clone, PartialEq, ::prost::Message)]
             pub struct BlockMetricsRecord {
                 #[prost(string, tag="1")]
@@ -221,6 +289,12 @@ pub mod bitcoin {
                 pub transaction: Option<TransactionMetrics>,
             }
             
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct TransactionMetricsRecords {
+                #[prost(message, repeated, tag="1")]
+                pub records: Vec<TransactionMetricsRecord>,
+            }
+            
             #[derive(Clone, PartialEq, ::prost::Message)]
             pub struct AddressActivityRecord {
                 #[prost(string, tag="1")]
@@ -229,6 +303,12 @@ pub mod bitcoin {
                 pub activity: Option<AddressActivity>,
             }
             
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct AddressActivityRecords {
+                #[prost(message, repeated, tag="1")]
+                pub records: Vec<AddressActivityRecord>,
+            }
+            
             #[derive(Clone, PartialEq, ::prost::Message)]
             pub struct NetworkDailyMetricsRecord {
                 #[prost(string, tag="1")]
@@ -236,6 +316,336 @@ pub mod bitcoin {
                 #[prost(message, optional, tag="2")]
                 pub metrics: Option<NetworkDailyMetrics>,
             }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct NetworkDailyMetricsRecords {
+                #[prost(message, repeated, tag="1")]
+                pub records: Vec<NetworkDailyMetricsRecord>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct EpochBoundary {
+                #[prost(uint64, tag="1")]
+                pub number: u64,
+                #[prost(int64, tag="2")]
+                pub time: i64,
+                #[prost(uint32, tag="3")]
+                pub bits: u32,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct EpochMetrics {
+                #[prost(uint64, tag="1")]
+                pub epoch: u64,
+                #[prost(uint64, tag="2")]
+                pub start_height: u64,
+                #[prost(uint64, tag="3")]
+                pub end_height: u64,
+                #[prost(int64, tag="4")]
+                pub start_time: i64,
+                #[prost(int64, tag="5")]
+                pub end_time: i64,
+                #[prost(int64, tag="6")]
+                pub actual_timespan: i64,
+                #[prost(int64, tag="7")]
+                pub target_timespan: i64,
+                #[prost(uint32, tag="8")]
+                pub bits: u32,
+                #[prost(uint32, tag="9")]
+                pub expected_bits: u32,
+                #[prost(uint32, tag="10")]
+                pub next_bits: u32,
+                #[prost(bool, tag="11")]
+                pub bits_match: bool,
+                #[prost(double, tag="12")]
+                pub adjustment_percent: f64,
+                #[prost(double, tag="13")]
+                pub hashrate: f64,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct EpochMetricsRecord {
+                #[prost(string, tag="1")]
+                pub table: String,
+                #[prost(message, optional, tag="2")]
+                pub metrics: Option<EpochMetrics>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct EpochMetricsRecords {
+                #[prost(message, repeated, tag="1")]
+                pub records: Vec<EpochMetricsRecord>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct DeploymentOutcome {
+                #[prost(string, tag="1")]
+                pub state: String,
+                #[prost(uint64, tag="2")]
+                pub period: u64,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct DeploymentPeriod {
+                #[prost(string, tag="1")]
+                pub deployment: String,
+                #[prost(uint32, tag="2")]
+                pub bit: u32,
+                #[prost(uint64, tag="3")]
+                pub period: u64,
+                #[prost(uint64, tag="4")]
+                pub start_height: u64,
+                #[prost(uint64, tag="5")]
+                pub end_height: u64,
+                #[prost(uint32, tag="6")]
+                pub signal_count: u32,
+                #[prost(uint32, tag="7")]
+                pub threshold: u32,
+                #[prost(uint32, tag="8")]
+                pub period_length: u32,
+                #[prost(string, tag="9")]
+                pub state: String,
+                #[prost(string, tag="10")]
+                pub next_state: String,
+                #[prost(bool, tag="11")]
+                pub transition: bool,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct DeploymentPeriodRecord {
+                #[prost(string, tag="1")]
+                pub table: String,
+                #[prost(message, optional, tag="2")]
+                pub period: Option<DeploymentPeriod>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct DeploymentPeriodRecords {
+                #[prost(message, repeated, tag="1")]
+                pub records: Vec<DeploymentPeriodRecord>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct MergedMiningCommitment {
+                #[prost(string, tag="1")]
+                pub protocol: String,
+                #[prost(string, tag="2")]
+                pub hash: String,
+                #[prost(int32, tag="3")]
+                pub output_index: i32,
+                #[prost(uint32, tag="4")]
+                pub merkle_size: u32,
+                #[prost(uint32, tag="5")]
+                pub nonce: u32,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct CoinbaseInfo {
+                #[prost(uint64, tag="1")]
+                pub block_height: u64,
+                #[prost(string, tag="2")]
+                pub tx_id: String,
+                #[prost(string, tag="3")]
+                pub script_sig: String,
+                #[prost(int64, tag="4")]
+                pub bip34_height: i64,
+                #[prost(bool, tag="5")]
+                pub bip34_height_mismatch: bool,
+                #[prost(string, tag="6")]
+                pub extranonce: String,
+                #[prost(string, repeated, tag="7")]
+                pub tags: Vec<String>,
+                #[prost(string, tag="8")]
+                pub witness_commitment: String,
+                #[prost(string, tag="9")]
+                pub witness_reserved_value: String,
+                #[prost(message, repeated, tag="10")]
+                pub merged_mining: Vec<MergedMiningCommitment>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct CoinbaseInfoRecord {
+                #[prost(message, optional, tag="1")]
+                pub coinbase: Option<CoinbaseInfo>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct BlockValidation {
+                #[prost(uint64, tag="1")]
+                pub block_height: u64,
+                #[prost(string, tag="2")]
+                pub block_hash: String,
+                #[prost(bool, tag="3")]
+                pub valid: bool,
+                #[prost(string, tag="4")]
+                pub merkle_root: String,
+                #[prost(string, tag="5")]
+                pub computed_merkle_root: String,
+                #[prost(string, tag="6")]
+                pub witness_commitment: String,
+                #[prost(string, tag="7")]
+                pub computed_witness_commitment: String,
+                #[prost(string, repeated, tag="8")]
+                pub mismatches: Vec<String>,
+                #[prost(string, tag="9")]
+                pub computed_block_hash: String,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct BlockValidationRecord {
+                #[prost(message, optional, tag="1")]
+                pub validation: Option<BlockValidation>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct TaprootInput {
+                #[prost(string, tag="1")]
+                pub tx_id: String,
+                #[prost(uint32, tag="2")]
+                pub input_index: u32,
+                #[prost(bool, tag="3")]
+                pub script_path: bool,
+                #[prost(bool, tag="4")]
+                pub has_annex: bool,
+                #[prost(string, tag="5")]
+                pub annex: String,
+                #[prost(uint32, tag="6")]
+                pub leaf_version: u32,
+                #[prost(uint32, tag="7")]
+                pub merkle_depth: u32,
+                #[prost(string, tag="8")]
+                pub internal_key: String,
+                #[prost(string, tag="9")]
+                pub tapscript: String,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct TaprootMetrics {
+                #[prost(uint64, tag="1")]
+                pub block_height: u64,
+                #[prost(uint64, tag="2")]
+                pub block_time: u64,
+                #[prost(uint32, tag="3")]
+                pub p2tr_outputs: u32,
+                #[prost(uint32, tag="4")]
+                pub key_path_spends: u32,
+                #[prost(uint32, tag="5")]
+                pub script_path_spends: u32,
+                #[prost(uint32, tag="6")]
+                pub annex_spends: u32,
+                #[prost(message, repeated, tag="7")]
+                pub inputs: Vec<TaprootInput>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct TaprootMetricsRecord {
+                #[prost(message, optional, tag="1")]
+                pub metrics: Option<TaprootMetrics>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct Inscription {
+                #[prost(uint64, tag="1")]
+                pub block_height: u64,
+                #[prost(uint64, tag="2")]
+                pub block_time: u64,
+                #[prost(string, tag="3")]
+                pub tx_id: String,
+                #[prost(uint32, tag="4")]
+                pub input_index: u32,
+                #[prost(uint32, tag="5")]
+                pub envelope_index: u32,
+                #[prost(string, tag="6")]
+                pub inscription_id: String,
+                #[prost(string, tag="7")]
+                pub content_type: String,
+                #[prost(string, tag="8")]
+                pub content_encoding: String,
+                #[prost(bool, tag="9")]
+                pub has_body: bool,
+                #[prost(uint64, tag="10")]
+                pub body_length: u64,
+                #[prost(string, repeated, tag="11")]
+                pub parents: Vec<String>,
+                #[prost(string, tag="12")]
+                pub metadata: String,
+                #[prost(string, tag="13")]
+                pub metaprotocol: String,
+                #[prost(bool, tag="14")]
+                pub has_pointer: bool,
+                #[prost(uint64, tag="15")]
+                pub pointer: u64,
+                #[prost(string, tag="16")]
+                pub delegate: String,
+                #[prost(bool, tag="17")]
+                pub duplicate_field: bool,
+                #[prost(bool, tag="18")]
+                pub incomplete_field: bool,
+                #[prost(bool, tag="19")]
+                pub unrecognized_even_field: bool,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct InscriptionRecord {
+                #[prost(string, tag="1")]
+                pub table: String,
+                #[prost(message, optional, tag="2")]
+                pub inscription: Option<Inscription>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct InscriptionRecords {
+                #[prost(message, repeated, tag="1")]
+                pub records: Vec<InscriptionRecord>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct SatRange {
+                #[prost(uint64, tag="1")]
+                pub start: u64,
+                #[prost(uint64, tag="2")]
+                pub end: u64,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct RareSat {
+                #[prost(uint64, tag="1")]
+                pub sat: u64,
+                #[prost(string, tag="2")]
+                pub rarity: String,
+                #[prost(uint64, tag="3")]
+                pub offset: u64,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct OutputSatRanges {
+                #[prost(uint64, tag="1")]
+                pub block_height: u64,
+                #[prost(string, tag="2")]
+                pub tx_id: String,
+                #[prost(uint32, tag="3")]
+                pub vout_index: u32,
+                #[prost(uint64, tag="4")]
+                pub value: u64,
+                #[prost(message, repeated, tag="5")]
+                pub ranges: Vec<SatRange>,
+                #[prost(uint64, tag="6")]
+                pub unknown_sats: u64,
+                #[prost(message, repeated, tag="7")]
+                pub rare_sats: Vec<RareSat>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct SatRangeRecord {
+                #[prost(message, optional, tag="1")]
+                pub output: Option<OutputSatRanges>,
+            }
+            
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct SatRangeRecords {
+                #[prost(message, repeated, tag="1")]
+                pub records: Vec<SatRangeRecord>,
+            }
         }
     }
 }
@@ -0,0 +1,52 @@
+use substreams::errors::Error;
+
+/// Number of satoshis in one bitcoin
+pub const SATOSHIS_PER_BTC: u64 = 100_000_000;
+
+/// An exact amount of bitcoin in satoshis, never above the 21M BTC supply cap
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    /// The 21M BTC supply cap, `MAX_MONEY` in Bitcoin Core
+    pub const MAX_MONEY: Amount = Amount(21_000_000 * SATOSHIS_PER_BTC);
+
+    /// Amount of `sat` satoshis, for values that were already validated (e.g. read from a store)
+    pub const fn from_sat(sat: u64) -> Amount {
+        Amount(sat)
+    }
+
+    pub const fn to_sat(self) -> u64 {
+        self.0
+    }
+
+    /// Convert a BTC-denominated value, as reported by Firehose, to the nearest satoshi
+    ///
+    /// Amounts up to the cap scale to at most 2.1e15 satoshis, well within the 53-bit
+    /// mantissa, so rounding the scaled value recovers the exact number of satoshis.
+    pub fn from_btc(btc: f64) -> Result<Amount, Error> {
+        if !btc.is_finite() || btc < 0.0 {
+            return Err(Error::msg(format!("Invalid BTC amount {}", btc)));
+        }
+        let sat = (btc * SATOSHIS_PER_BTC as f64).round();
+        if sat > Self::MAX_MONEY.0 as f64 {
+            return Err(Error::msg(format!("BTC amount {} exceeds the 21M BTC supply cap", btc)));
+        }
+        Ok(Amount(sat as u64))
+    }
+
+    /// Add two amounts, `None` when the sum exceeds the supply cap
+    pub fn checked_add(self, rhs: Amount) -> Option<Amount> {
+        self.0
+            .checked_add(rhs.0)
+            .filter(|sat| *sat <= Self::MAX_MONEY.0)
+            .map(Amount)
+    }
+
+    /// Subtract two amounts, clamping at zero
+    pub fn saturating_sub(self, rhs: Amount) -> Amount {
+        Amount(self.0.saturating_sub(rhs.0))
+    }
+}
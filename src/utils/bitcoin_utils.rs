@@ -1,48 +1,19 @@
-/// Parse an output script to determine its type
-pub fn parse_output_script(script_bytes: &[u8]) -> String {
-    if script_bytes.is_empty() {
-        return "UNKNOWN".to_string();
-    }
-    
-    // Simple script type detection based on common patterns
-    // This is a simplified implementation and doesn't cover all script types
-    
-    // P2PKH: OP_DUP OP_HASH160 <pubKeyHash> OP_EQUALVERIFY OP_CHECKSIG
-    if script_bytes.len() >= 25 && script_bytes[0] == 0x76 && script_bytes[1] == 0xa9 {
-        return "P2PKH".to_string();
-    }
-    
-    // P2SH: OP_HASH160 <scriptHash> OP_EQUAL
-    if script_bytes.len() >= 23 && script_bytes[0] == 0xa9 {
-        return "P2SH".to_string();
-    }
-    
-    // P2WPKH: OP_0 <pubKeyHash>
-    if script_bytes.len() >= 22 && script_bytes[0] == 0x00 && script_bytes[1] == 0x14 {
-        return "P2WPKH".to_string();
-    }
-    
-    // P2WSH: OP_0 <scriptHash>
-    if script_bytes.len() >= 34 && script_bytes[0] == 0x00 && script_bytes[1] == 0x20 {
-        return "P2WSH".to_string();
-    }
-    
-    // P2TR: OP_1 <x-only pubkey>
-    if script_bytes.len() >= 34 && script_bytes[0] == 0x51 && script_bytes[1] == 0x20 {
-        return "P2TR".to_string();
-    }
-    
-    // Multisig: OP_<M> <pubKey1> ... <pubKeyN> OP_<N> OP_CHECKMULTISIG
-    if script_bytes.len() > 3 && script_bytes[script_bytes.len() - 1] == 0xae {
-        return "MULTISIG".to_string();
-    }
-    
-    // OP_RETURN: OP_RETURN <data>
-    if script_bytes.len() >= 1 && script_bytes[0] == 0x6a {
-        return "OP_RETURN".to_string();
-    }
-    
-    "UNKNOWN".to_string()
+use super::amount::Amount;
+use super::script::{self, ScriptType};
+use substreams::errors::Error;
+
+/// Total value of the outputs of a transaction
+pub fn transaction_output_value(tx: &substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Transaction) -> Result<Amount, Error> {
+    tx.vout.iter().try_fold(Amount::ZERO, |total, vout| {
+        total
+            .checked_add(Amount::from_btc(vout.value)?)
+            .ok_or_else(|| Error::msg(format!("Outputs of transaction {} exceed the supply cap", tx.txid)))
+    })
+}
+
+/// Check if a transaction is the coinbase transaction of its block
+pub fn is_coinbase_transaction(tx: &substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Transaction) -> bool {
+    tx.vin.first().is_some_and(|vin| !vin.coinbase.is_empty())
 }
 
 /// Check if a transaction is a SegWit transaction
@@ -78,22 +49,54 @@ pub fn is_taproot_transaction(tx: &substreams_bitcoin::pb::sf::bitcoin::r#type::
     false
 }
 
-/// Extract the miner name from a coinbase transaction
-pub fn extract_miner_name(_tx: &substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Transaction) -> String {
-    // In a real implementation, we would extract the miner name from the coinbase transaction
-    // For now, return a placeholder
-    "Unknown Miner".to_string()
-}
+/// Base58Check version byte of mainnet P2PKH addresses
+const P2PKH_VERSION_MAINNET: u8 = 0x00;
+/// Base58Check version byte of mainnet P2SH addresses
+const P2SH_VERSION_MAINNET: u8 = 0x05;
+/// Base58Check version byte of testnet P2PKH addresses
+const P2PKH_VERSION_TESTNET: u8 = 0x6f;
+/// Base58Check version byte of testnet P2SH addresses
+const P2SH_VERSION_TESTNET: u8 = 0xc4;
 
 /// Extract a Bitcoin address from an output script
-pub fn extract_address_from_script(script_bytes: &[u8], _testnet: bool) -> Option<String> {
-    // In a real implementation, this would use proper Bitcoin address encoding
-    // This is a simplified placeholder that doesn't actually decode addresses
-    if script_bytes.is_empty() {
-        return None;
+///
+/// P2PKH and P2SH scripts are encoded with Base58Check, witness programs with
+/// Bech32 (version 0) or Bech32m (version 1 and above) as per BIP173/BIP350.
+/// Scripts without a standard address form (P2PK, bare multisig, OP_RETURN, ...)
+/// return `None`.
+pub fn extract_address_from_script(script_bytes: &[u8], testnet: bool) -> Option<String> {
+    // The templates are length-checked by classify, only the encoding is done here
+    match script::classify(script_bytes) {
+        ScriptType::P2pkh => {
+            let version = if testnet { P2PKH_VERSION_TESTNET } else { P2PKH_VERSION_MAINNET };
+            Some(encode_base58_check(version, &script_bytes[3..23]))
+        }
+        ScriptType::P2sh => {
+            let version = if testnet { P2SH_VERSION_TESTNET } else { P2SH_VERSION_MAINNET };
+            Some(encode_base58_check(version, &script_bytes[2..22]))
+        }
+        ScriptType::P2wpkh
+        | ScriptType::P2wsh
+        | ScriptType::P2tr
+        | ScriptType::P2a
+        | ScriptType::WitnessUnknown { .. } => {
+            let (version, program) = script::witness_program(script_bytes)?;
+            encode_segwit_address(version, program, testnet)
+        }
+        _ => None,
     }
-    
-    // Just returning a hex representation of the script for illustration
-    // Real implementation would decode to actual bitcoin addresses
-    Some(hex::encode(script_bytes))
+}
+
+/// Encode a payload with a version byte using Base58Check
+pub fn encode_base58_check(version: u8, payload: &[u8]) -> String {
+    bs58::encode(payload).with_check_version(version).into_string()
+}
+
+/// Encode a witness program as a SegWit address
+///
+/// Version 0 programs use Bech32 and must be 20 or 32 bytes long, later versions use Bech32m.
+pub fn encode_segwit_address(version: u8, program: &[u8], testnet: bool) -> Option<String> {
+    let hrp = if testnet { bech32::hrp::TB } else { bech32::hrp::BC };
+    let version = bech32::Fe32::try_from(version).ok()?;
+    bech32::segwit::encode(hrp, version, program).ok()
 }
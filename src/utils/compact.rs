@@ -0,0 +1,112 @@
+use primitive_types::U256;
+use substreams::errors::Error;
+use substreams::scalar::BigInt;
+
+/// Parse the compact target of a block header, reported by Firehose as hex (e.g. "1d00ffff")
+pub fn parse_bits(bits: &str) -> Result<u32, Error> {
+    u32::from_str_radix(bits, 16).map_err(|_| Error::msg(format!("Invalid compact bits {:?}", bits)))
+}
+
+/// Decode a compact target into the 256-bit target, as `arith_uint256::SetCompact`
+///
+/// The top byte is the size of the target in bytes and the low 23 bits its mantissa;
+/// targets with the sign bit set or that don't fit in 256 bits are rejected.
+pub fn decode_compact(bits: u32) -> Result<U256, Error> {
+    let size = (bits >> 24) as usize;
+    let mantissa = bits & 0x007f_ffff;
+
+    if mantissa != 0 && bits & 0x0080_0000 != 0 {
+        return Err(Error::msg(format!("Negative compact target {:08x}", bits)));
+    }
+    if mantissa != 0 && (size > 34 || (mantissa > 0xff && size > 33) || (mantissa > 0xffff && size > 32)) {
+        return Err(Error::msg(format!("Compact target {:08x} overflows 256 bits", bits)));
+    }
+
+    Ok(if size <= 3 {
+        U256::from(mantissa >> (8 * (3 - size)))
+    } else {
+        U256::from(mantissa) << (8 * (size - 3))
+    })
+}
+
+/// Encode a 256-bit target in compact form, as `arith_uint256::GetCompact`
+///
+/// The mantissa is truncated to its 3 most significant bytes, so decoding may lose precision.
+pub fn encode_compact(target: U256) -> u32 {
+    let mut size = target.bits().div_ceil(8);
+    let mut compact = if size <= 3 {
+        target.low_u32() << (8 * (3 - size))
+    } else {
+        (target >> (8 * (size - 3))).low_u32()
+    };
+    // Keep the sign bit clear by moving to a larger size
+    if compact & 0x0080_0000 != 0 {
+        compact >>= 8;
+        size += 1;
+    }
+    compact | (size as u32) << 24
+}
+
+/// Difficulty of a compact target relative to the minimum difficulty, as the RPC `difficulty`
+pub fn difficulty(bits: u32) -> f64 {
+    let mut shift = (bits >> 24) & 0xff;
+    let mantissa = bits & 0x00ff_ffff;
+    if mantissa == 0 {
+        return 0.0;
+    }
+    let mut difficulty = 0x0000_ffff as f64 / mantissa as f64;
+    while shift < 29 {
+        difficulty *= 256.0;
+        shift += 1;
+    }
+    while shift > 29 {
+        difficulty /= 256.0;
+        shift -= 1;
+    }
+    difficulty
+}
+
+/// Expected number of hashes to find a block at `target`, `2^256 / (target + 1)`
+///
+/// Computed as `~target / (target + 1) + 1` so it stays within 256 bits, as `GetBlockProof`.
+pub fn block_work(target: U256) -> U256 {
+    if target.is_zero() {
+        return U256::zero();
+    }
+    match target.checked_add(U256::one()) {
+        Some(divisor) => (!target / divisor) + 1,
+        // 2^256 / 2^256
+        None => U256::one(),
+    }
+}
+
+/// Parse a 256-bit value written as hex, such as the `chainwork` of a Firehose block
+pub fn parse_u256(value: &str) -> Result<U256, Error> {
+    U256::from_str_radix(value, 16).map_err(|_| Error::msg(format!("Invalid 256-bit hex value {:?}", value)))
+}
+
+/// Write a 256-bit value as 64 hex digits, the format of the RPC `chainwork` field
+pub fn to_hex_u256(value: U256) -> String {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Convert a 256-bit value to a store `BigInt`
+pub fn u256_to_bigint(value: U256) -> BigInt {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    BigInt::from_unsigned_bytes_be(&bytes)
+}
+
+/// Convert a store `BigInt` back to a 256-bit value, `None` when negative or too large
+pub fn bigint_to_u256(value: &BigInt) -> Option<U256> {
+    if *value < BigInt::zero() {
+        return None;
+    }
+    let (_, bytes) = value.to_bytes_be();
+    if bytes.len() > 32 {
+        return None;
+    }
+    Some(U256::from_big_endian(&bytes))
+}
@@ -0,0 +1,73 @@
+use serde::Deserialize;
+use substreams::errors::Error;
+
+/// Mainnet BIP9 deployments embedded in the package
+const EMBEDDED_DEPLOYMENTS: &str = include_str!("deployments.json");
+
+/// Blocks per signaling period on mainnet
+const DEFAULT_PERIOD: u64 = 2016;
+
+/// How `start` and `timeout` of a deployment are measured
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivationMode {
+    /// BIP9: median time past of the last block of the previous period
+    #[default]
+    Bip9,
+    /// BIP8: height of the first block of the period
+    Bip8,
+}
+
+/// A soft-fork deployment signaled through version bits
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Deployment {
+    pub name: String,
+    /// Version bit, 0 to 28
+    pub bit: u8,
+    pub start: i64,
+    pub timeout: i64,
+    /// Signaling blocks needed in a period to lock in
+    pub threshold: u32,
+    #[serde(default = "default_period")]
+    pub period: u64,
+    #[serde(default)]
+    pub min_activation_height: u64,
+    #[serde(default)]
+    pub mode: ActivationMode,
+    /// BIP8 only: lock in when the timeout is reached instead of failing
+    #[serde(default)]
+    pub lock_in_on_timeout: bool,
+}
+
+fn default_period() -> u64 {
+    DEFAULT_PERIOD
+}
+
+/// Deployments tracked by the version bits modules
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Deployments {
+    #[serde(default)]
+    pub deployments: Vec<Deployment>,
+}
+
+impl Deployments {
+    /// Parse deployments from JSON
+    pub fn from_json(json: &str) -> Result<Deployments, Error> {
+        let deployments: Deployments =
+            serde_json::from_str(json).map_err(|e| Error::msg(format!("invalid deployments: {}", e)))?;
+        for deployment in &deployments.deployments {
+            if deployment.bit > 28 || deployment.period == 0 || deployment.name.is_empty() {
+                return Err(Error::msg(format!("invalid deployment {:?}", deployment.name)));
+            }
+        }
+        Ok(deployments)
+    }
+
+    /// Load the deployments of `params`, or the embedded mainnet deployments when empty
+    pub fn load(params: &str) -> Result<Deployments, Error> {
+        if params.trim().is_empty() {
+            return Deployments::from_json(EMBEDDED_DEPLOYMENTS);
+        }
+        Deployments::from_json(params)
+    }
+}
@@ -0,0 +1,32 @@
+use sha2::{Digest, Sha256};
+
+/// Double SHA-256, the hash of transactions, headers and merkle nodes
+pub fn sha256d(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+/// Hash of two merkle nodes
+fn merkle_parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(left);
+    data[32..].copy_from_slice(right);
+    sha256d(&data)
+}
+
+/// Merkle root of leaves in internal byte order, as `ComputeMerkleRoot`
+///
+/// Odd levels pair their last node with itself. The second value tells whether two identical
+/// nodes were paired on purpose, which lets a different list of leaves share the same root
+/// (CVE-2012-2459). The root of no leaves is all zeros.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> ([u8; 32], bool) {
+    let mut level = leaves.to_vec();
+    let mut mutated = false;
+    while level.len() > 1 {
+        mutated |= level.chunks_exact(2).any(|pair| pair[0] == pair[1]);
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_parent(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect();
+    }
+    (level.first().copied().unwrap_or_default(), mutated)
+}
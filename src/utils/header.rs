@@ -0,0 +1,46 @@
+use super::compact;
+use super::hashes;
+use super::ids::{BlockHash, TxMerkleRoot};
+use primitive_types::U256;
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+/// Size of a serialized block header
+pub const HEADER_SIZE: usize = 80;
+
+/// Serialize the 80-byte header of a block: version, previous block hash, merkle root, time,
+/// bits and nonce, integers little-endian and hashes in internal byte order
+pub fn serialize_header(block: &Block) -> Result<[u8; HEADER_SIZE], Error> {
+    // The genesis block has no previous block
+    let previous_hash = match block.previous_hash.as_str() {
+        "" => BlockHash::default(),
+        hash => BlockHash::from_hex(hash)?,
+    };
+    let time = u32::try_from(block.time)
+        .map_err(|_| Error::msg(format!("Block {} time {} doesn't fit a header", block.height, block.time)))?;
+
+    let mut header = [0u8; HEADER_SIZE];
+    header[0..4].copy_from_slice(&block.version.to_le_bytes());
+    header[4..36].copy_from_slice(&previous_hash.to_byte_array());
+    header[36..68].copy_from_slice(&TxMerkleRoot::from_hex(&block.merkle_root)?.to_byte_array());
+    header[68..72].copy_from_slice(&time.to_le_bytes());
+    header[72..76].copy_from_slice(&compact::parse_bits(&block.bits)?.to_le_bytes());
+    header[76..80].copy_from_slice(&block.nonce.to_le_bytes());
+    Ok(header)
+}
+
+/// Hash of a block header, computed from the block fields
+pub fn header_hash(block: &Block) -> Result<BlockHash, Error> {
+    Ok(BlockHash::from_byte_array(hashes::sha256d(&serialize_header(block)?)))
+}
+
+/// Whether a block hash meets the target encoded in `bits`, as `CheckProofOfWork`
+///
+/// The hash is read as a little-endian 256-bit number and must not exceed the target.
+pub fn check_proof_of_work(hash: BlockHash, bits: u32) -> Result<bool, Error> {
+    let target = compact::decode_compact(bits)?;
+    if target.is_zero() {
+        return Ok(false);
+    }
+    Ok(U256::from_little_endian(&hash.to_byte_array()) <= target)
+}
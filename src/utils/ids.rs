@@ -0,0 +1,65 @@
+use std::fmt;
+use substreams::errors::Error;
+
+/// Define a 32-byte double-SHA256 identifier
+///
+/// Bytes are kept in internal (serialization) order, while hex strings always use the
+/// reversed RPC display order, as in Firehose blocks and block explorers.
+macro_rules! hash_type {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name([u8; 32]);
+
+        #[allow(dead_code)]
+        impl $name {
+            /// Parse a hex string in display order
+            pub fn from_hex(display_hex: &str) -> Result<Self, Error> {
+                let mut bytes: [u8; 32] = hex::decode(display_hex)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| Error::msg(format!("Invalid {} {:?}", stringify!($name), display_hex)))?;
+                bytes.reverse();
+                Ok(Self(bytes))
+            }
+
+            /// Build from bytes in internal order, e.g. a double-SHA256 digest
+            pub const fn from_byte_array(bytes: [u8; 32]) -> Self {
+                Self(bytes)
+            }
+
+            /// Bytes in internal order
+            pub const fn to_byte_array(self) -> [u8; 32] {
+                self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.iter().rev().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+        }
+    };
+}
+
+hash_type!(
+    /// Transaction ID, the hash of the transaction without its witness data
+    Txid
+);
+
+hash_type!(
+    /// Witness transaction ID, the hash of the transaction with its witness data (BIP141)
+    ///
+    /// Equal to the txid for transactions without witness data.
+    Wtxid
+);
+
+hash_type!(
+    /// Block hash, the hash of the 80-byte block header
+    BlockHash
+);
+
+hash_type!(
+    /// Merkle root of the txids of a block, as committed in its header
+    TxMerkleRoot
+);
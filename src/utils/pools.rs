@@ -0,0 +1,119 @@
+use super::bitcoin_utils;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use substreams::errors::Error;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Transaction;
+
+/// Pool database embedded in the package, in the shape of the public known-pools JSON
+const EMBEDDED_POOLS: &str = include_str!("pools.json");
+
+/// A mining pool entry of the database
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct Pool {
+    pub name: String,
+    #[serde(default)]
+    pub link: String,
+}
+
+/// Known mining pools, indexed by coinbase tag and by payout address
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PoolDatabase {
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub coinbase_tags: BTreeMap<String, Pool>,
+    #[serde(default)]
+    pub payout_addresses: BTreeMap<String, Pool>,
+}
+
+/// How a block was attributed to a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolMatchMethod {
+    PayoutAddress,
+    CoinbaseTag,
+    Unknown,
+}
+
+impl PoolMatchMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PoolMatchMethod::PayoutAddress => "PAYOUT_ADDRESS",
+            PoolMatchMethod::CoinbaseTag => "COINBASE_TAG",
+            PoolMatchMethod::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// Result of a pool attribution
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolMatch {
+    pub name: String,
+    pub method: PoolMatchMethod,
+}
+
+impl PoolDatabase {
+    /// Parse a pool database from JSON
+    pub fn from_json(json: &str) -> Result<PoolDatabase, Error> {
+        serde_json::from_str(json).map_err(|e| Error::msg(format!("invalid pool database: {}", e)))
+    }
+
+    /// Load the embedded database, with the entries of `params` (same JSON shape) taking precedence
+    pub fn load(params: &str) -> Result<PoolDatabase, Error> {
+        let mut database = PoolDatabase::from_json(EMBEDDED_POOLS)?;
+        if params.trim().is_empty() {
+            return Ok(database);
+        }
+
+        let overrides = PoolDatabase::from_json(params)?;
+        database.coinbase_tags.extend(overrides.coinbase_tags);
+        database.payout_addresses.extend(overrides.payout_addresses);
+        if !overrides.version.is_empty() {
+            database.version = overrides.version;
+        }
+        Ok(database)
+    }
+
+    /// Attribute a block to a pool from its coinbase transaction
+    ///
+    /// Payout addresses are checked first as they are the most specific, then the
+    /// longest coinbase tag found in the ASCII of the coinbase scriptSig.
+    pub fn identify(&self, coinbase_tx: &Transaction) -> PoolMatch {
+        for vout in &coinbase_tx.vout {
+            let Some(script) = &vout.script_pub_key else {
+                continue;
+            };
+            let script_bytes = hex::decode(&script.hex).unwrap_or_default();
+            if let Some(pool) = bitcoin_utils::extract_address_from_script(&script_bytes, false)
+                .and_then(|address| self.payout_addresses.get(&address))
+            {
+                return PoolMatch {
+                    name: pool.name.clone(),
+                    method: PoolMatchMethod::PayoutAddress,
+                };
+            }
+        }
+
+        let coinbase_bytes = coinbase_tx
+            .vin
+            .first()
+            .and_then(|vin| hex::decode(&vin.coinbase).ok())
+            .unwrap_or_default();
+        let coinbase_text = String::from_utf8_lossy(&coinbase_bytes);
+        let tag_match = self
+            .coinbase_tags
+            .iter()
+            .filter(|(tag, _)| coinbase_text.contains(tag.as_str()))
+            .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)));
+        if let Some((_, pool)) = tag_match {
+            return PoolMatch {
+                name: pool.name.clone(),
+                method: PoolMatchMethod::CoinbaseTag,
+            };
+        }
+
+        PoolMatch {
+            name: "Unknown".to_string(),
+            method: PoolMatchMethod::Unknown,
+        }
+    }
+}
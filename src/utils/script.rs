@@ -0,0 +1,253 @@
+use substreams::errors::Error;
+
+// Opcodes
+pub const OP_0: u8 = 0x00;
+pub const OP_PUSHDATA1: u8 = 0x4c;
+pub const OP_PUSHDATA2: u8 = 0x4d;
+pub const OP_PUSHDATA4: u8 = 0x4e;
+pub const OP_1NEGATE: u8 = 0x4f;
+pub const OP_1: u8 = 0x51;
+pub const OP_16: u8 = 0x60;
+pub const OP_IF: u8 = 0x63;
+pub const OP_ENDIF: u8 = 0x68;
+pub const OP_RETURN: u8 = 0x6a;
+pub const OP_DUP: u8 = 0x76;
+pub const OP_EQUAL: u8 = 0x87;
+pub const OP_EQUALVERIFY: u8 = 0x88;
+pub const OP_HASH160: u8 = 0xa9;
+pub const OP_CHECKSIG: u8 = 0xac;
+pub const OP_CHECKMULTISIG: u8 = 0xae;
+
+/// Largest script that can be executed, `MAX_SCRIPT_SIZE`
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
+
+/// An instruction of a script
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    /// Data pushed by OP_0, a direct push or OP_PUSHDATA1/2/4
+    PushBytes(&'a [u8]),
+    /// Any other opcode, including OP_1NEGATE and OP_1 to OP_16
+    Op(u8),
+}
+
+/// Iterator over the instructions of a script, ending after the first truncated push
+pub struct Instructions<'a> {
+    script: &'a [u8],
+    position: usize,
+}
+
+/// Iterate over the instructions of a script
+pub fn instructions(script: &[u8]) -> Instructions<'_> {
+    Instructions {
+        script,
+        position: 0,
+    }
+}
+
+impl<'a> Instructions<'a> {
+    /// Offset of the next instruction in the script, its end once a push is truncated
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn read(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.position.checked_add(len).filter(|end| *end <= self.script.len());
+        let Some(end) = end else {
+            self.position = self.script.len();
+            return Err(Error::msg("Truncated push in script"));
+        };
+        let bytes = &self.script[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_len(&mut self, size: usize) -> Result<usize, Error> {
+        let bytes = self.read(size)?;
+        Ok(bytes.iter().rev().fold(0usize, |len, byte| len << 8 | *byte as usize))
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let opcode = *self.script.get(self.position)?;
+        self.position += 1;
+        let len = match opcode {
+            0x01..=0x4b => Ok(opcode as usize),
+            OP_PUSHDATA1 => self.read_len(1),
+            OP_PUSHDATA2 => self.read_len(2),
+            OP_PUSHDATA4 => self.read_len(4),
+            OP_0 => Ok(0),
+            _ => return Some(Ok(Instruction::Op(opcode))),
+        };
+        Some(len.and_then(|len| self.read(len)).map(Instruction::PushBytes))
+    }
+}
+
+/// Decode a script number (`CScriptNum`): little-endian with the sign in the top bit of the last byte
+///
+/// Numbers longer than 8 bytes are rejected, as are non-minimal encodings when `minimal` is set.
+pub fn read_script_num(bytes: &[u8], minimal: bool) -> Option<i64> {
+    let Some(last) = bytes.last() else {
+        return Some(0);
+    };
+    if bytes.len() > 8 {
+        return None;
+    }
+    // The last byte may only be 0x00 or 0x80 when the byte before needs the sign bit
+    if minimal && last & 0x7f == 0 && (bytes.len() == 1 || bytes[bytes.len() - 2] & 0x80 == 0) {
+        return None;
+    }
+
+    let magnitude = bytes
+        .iter()
+        .enumerate()
+        .fold(0u64, |value, (i, byte)| value | (*byte as u64) << (8 * i));
+    let sign_bit = 0x80u64 << (8 * (bytes.len() - 1));
+    let value = (magnitude & !sign_bit) as i64;
+    Some(if magnitude & sign_bit != 0 { -value } else { value })
+}
+
+/// Value of a small integer opcode, OP_0 and OP_1 to OP_16
+pub fn small_int_value(opcode: u8) -> Option<u8> {
+    match opcode {
+        OP_0 => Some(0),
+        OP_1..=OP_16 => Some(opcode - OP_1 + 1),
+        _ => None,
+    }
+}
+
+/// Type of an output script, recognized by exact templates as Bitcoin Core's `Solver`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptType {
+    /// <pubkey> OP_CHECKSIG
+    P2pk { compressed: bool },
+    /// OP_DUP OP_HASH160 <20 bytes> OP_EQUALVERIFY OP_CHECKSIG
+    P2pkh,
+    /// OP_HASH160 <20 bytes> OP_EQUAL
+    P2sh,
+    /// OP_0 <20 bytes>
+    P2wpkh,
+    /// OP_0 <32 bytes>
+    P2wsh,
+    /// OP_1 <32 bytes>
+    P2tr,
+    /// OP_1 <0x4e73>, the pay-to-anchor output
+    P2a,
+    /// Witness program of version 1 to 16 without a defined meaning yet
+    WitnessUnknown { version: u8 },
+    /// OP_m <pubkey>... OP_n OP_CHECKMULTISIG
+    Multisig { required: u8, total: u8 },
+    /// OP_RETURN followed by pushes only
+    OpReturn,
+    NonStandard,
+}
+
+impl ScriptType {
+    /// Name of the type, as stored in `script_type` columns
+    pub fn name(&self) -> String {
+        match self {
+            ScriptType::P2pk { .. } => "P2PK".to_string(),
+            ScriptType::P2pkh => "P2PKH".to_string(),
+            ScriptType::P2sh => "P2SH".to_string(),
+            ScriptType::P2wpkh => "P2WPKH".to_string(),
+            ScriptType::P2wsh => "P2WSH".to_string(),
+            ScriptType::P2tr => "P2TR".to_string(),
+            ScriptType::P2a => "P2A".to_string(),
+            ScriptType::WitnessUnknown { version } => format!("WITNESS_V{}", version),
+            ScriptType::Multisig { .. } => "MULTISIG".to_string(),
+            ScriptType::OpReturn => "OP_RETURN".to_string(),
+            ScriptType::NonStandard => "NONSTANDARD".to_string(),
+        }
+    }
+}
+
+/// Whether bytes have the size of a public key with a matching header byte
+pub fn is_pubkey(bytes: &[u8]) -> bool {
+    match bytes.first() {
+        Some(0x02 | 0x03) => bytes.len() == 33,
+        Some(0x04 | 0x06 | 0x07) => bytes.len() == 65,
+        _ => false,
+    }
+}
+
+/// Whether an output script can never be spent, as `CScript::IsUnspendable`: it starts with
+/// OP_RETURN, whatever follows, or is too large to execute
+pub fn is_unspendable(script: &[u8]) -> bool {
+    script.first() == Some(&OP_RETURN) || script.len() > MAX_SCRIPT_SIZE
+}
+
+/// Version and program of a witness program: a small integer then a direct push of 2 to 40 bytes
+pub fn witness_program(script: &[u8]) -> Option<(u8, &[u8])> {
+    let (&version_opcode, rest) = script.split_first()?;
+    let (&push, program) = rest.split_first()?;
+    if !(4..=42).contains(&script.len()) || push as usize != program.len() {
+        return None;
+    }
+    let version = match version_opcode {
+        OP_0 => 0,
+        OP_1..=OP_16 => small_int_value(version_opcode)?,
+        _ => return None,
+    };
+    Some((version, program))
+}
+
+/// Parse an m-of-n bare multisig script
+fn multisig(script: &[u8]) -> Option<ScriptType> {
+    let instructions: Vec<Instruction> = instructions(script).collect::<Result<_, _>>().ok()?;
+    let (Instruction::Op(first), Instruction::Op(last)) = (instructions.first()?, instructions.last()?) else {
+        return None;
+    };
+    if *last != OP_CHECKMULTISIG || instructions.len() < 4 {
+        return None;
+    }
+    let Instruction::Op(total_opcode) = instructions[instructions.len() - 2] else {
+        return None;
+    };
+    let required = small_int_value(*first).filter(|required| *required >= 1)?;
+    let total = small_int_value(total_opcode).filter(|total| *total >= required)?;
+
+    let keys = &instructions[1..instructions.len() - 2];
+    let all_keys = keys
+        .iter()
+        .all(|instruction| matches!(instruction, Instruction::PushBytes(bytes) if is_pubkey(bytes)));
+    (all_keys && keys.len() == total as usize).then_some(ScriptType::Multisig { required, total })
+}
+
+/// Classify an output script
+pub fn classify(script: &[u8]) -> ScriptType {
+    match script {
+        [OP_DUP, OP_HASH160, 0x14, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG] if hash.len() == 20 => {
+            return ScriptType::P2pkh
+        }
+        [OP_HASH160, 0x14, hash @ .., OP_EQUAL] if hash.len() == 20 => return ScriptType::P2sh,
+        [push, key @ .., OP_CHECKSIG] if *push as usize == key.len() && is_pubkey(key) => {
+            return ScriptType::P2pk {
+                compressed: key.len() == 33,
+            }
+        }
+        [OP_RETURN, rest @ ..] => {
+            let push_only = instructions(rest).all(|instruction| match instruction {
+                Ok(Instruction::PushBytes(_)) => true,
+                Ok(Instruction::Op(opcode)) => opcode <= OP_16,
+                Err(_) => false,
+            });
+            return if push_only { ScriptType::OpReturn } else { ScriptType::NonStandard };
+        }
+        _ => {}
+    }
+
+    if let Some((version, program)) = witness_program(script) {
+        return match (version, program.len()) {
+            (0, 20) => ScriptType::P2wpkh,
+            (0, 32) => ScriptType::P2wsh,
+            (0, _) => ScriptType::NonStandard,
+            (1, 32) => ScriptType::P2tr,
+            (1, 2) if program == [0x4e, 0x73] => ScriptType::P2a,
+            (version, _) => ScriptType::WitnessUnknown { version },
+        };
+    }
+
+    multisig(script).unwrap_or(ScriptType::NonStandard)
+}
@@ -0,0 +1,61 @@
+use crate::utils::amount::{Amount, SATOSHIS_PER_BTC};
+use proptest::prelude::*;
+
+/// Decimal BTC text as found in Firehose JSON, e.g. "0.00012345"
+fn btc_text(sat: u64) -> String {
+    format!("{}.{:08}", sat / SATOSHIS_PER_BTC, sat % SATOSHIS_PER_BTC)
+}
+
+#[test]
+fn test_from_btc() {
+    assert_eq!(Amount::from_btc(0.5).unwrap(), Amount::from_sat(50_000_000));
+    assert_eq!(Amount::from_btc(0.1).unwrap(), Amount::from_sat(10_000_000));
+    assert_eq!(Amount::from_btc(6.25001).unwrap(), Amount::from_sat(625_001_000));
+    assert_eq!(Amount::from_btc(0.00000001).unwrap(), Amount::from_sat(1));
+    assert_eq!(Amount::from_btc(0.0).unwrap(), Amount::ZERO);
+    assert_eq!(Amount::from_btc(21_000_000.0).unwrap(), Amount::MAX_MONEY);
+}
+
+#[test]
+fn test_from_btc_rejects_invalid_values() {
+    assert!(Amount::from_btc(21_000_000.00000001).is_err());
+    assert!(Amount::from_btc(-0.00000001).is_err());
+    assert!(Amount::from_btc(f64::NAN).is_err());
+    assert!(Amount::from_btc(f64::INFINITY).is_err());
+}
+
+#[test]
+fn test_checked_add() {
+    let one = Amount::from_sat(1);
+    assert_eq!(one.checked_add(one), Some(Amount::from_sat(2)));
+    assert_eq!(Amount::MAX_MONEY.checked_add(Amount::ZERO), Some(Amount::MAX_MONEY));
+    assert_eq!(Amount::MAX_MONEY.checked_add(one), None);
+    assert_eq!(Amount::from_sat(u64::MAX).checked_add(one), None);
+    assert_eq!(one.saturating_sub(Amount::from_sat(2)), Amount::ZERO);
+}
+
+proptest! {
+    #[test]
+    fn prop_from_btc_round_trips_every_satoshi_amount(sat in 0..=Amount::MAX_MONEY.to_sat()) {
+        let btc: f64 = btc_text(sat).parse().unwrap();
+        prop_assert_eq!(Amount::from_btc(btc).unwrap().to_sat(), sat);
+        prop_assert_eq!(Amount::from_btc(sat as f64 / SATOSHIS_PER_BTC as f64).unwrap().to_sat(), sat);
+    }
+
+    // Off-grid values only come from float noise, checked up to 10k BTC where it stays far below 0.1 sat
+    #[test]
+    fn prop_from_btc_rounds_to_nearest_satoshi(sat in 0..10_000 * SATOSHIS_PER_BTC, offset in -0.4f64..0.4) {
+        let btc = (sat as f64 + offset) / SATOSHIS_PER_BTC as f64;
+        if btc >= 0.0 {
+            prop_assert_eq!(Amount::from_btc(btc).unwrap().to_sat(), sat);
+        }
+    }
+
+    #[test]
+    fn prop_checked_add_never_exceeds_cap(a in 0..=Amount::MAX_MONEY.to_sat(), b in 0..=Amount::MAX_MONEY.to_sat()) {
+        match Amount::from_sat(a).checked_add(Amount::from_sat(b)) {
+            Some(sum) => prop_assert_eq!(sum.to_sat(), a + b),
+            None => prop_assert!(a + b > Amount::MAX_MONEY.to_sat()),
+        }
+    }
+}
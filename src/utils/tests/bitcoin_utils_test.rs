@@ -0,0 +1,82 @@
+use crate::utils::bitcoin_utils::extract_address_from_script;
+
+fn address(script_hex: &str, testnet: bool) -> Option<String> {
+    extract_address_from_script(&hex::decode(script_hex).unwrap(), testnet)
+}
+
+#[test]
+fn test_p2pkh_address() {
+    // Genesis block coinbase key
+    assert_eq!(
+        address("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac", false).unwrap(),
+        "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"
+    );
+    assert_eq!(
+        address("76a914243f1394f44554f4ce3fd68649c19adc483ce92488ac", true).unwrap(),
+        "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn"
+    );
+}
+
+#[test]
+fn test_p2sh_address() {
+    assert_eq!(
+        address("a914e9c3dd0c07aac76179ebc76a6c78d4d67c6c160a87", false).unwrap(),
+        "3P14159f73E4gFr7JterCCQh9QjiTjiZrG"
+    );
+}
+
+#[test]
+fn test_bip173_bip350_vectors() {
+    let vectors = [
+        ("0014751e76e8199196d454941c45d1b3a323f1433bd6", false, "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+        (
+            "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262",
+            true,
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+        ),
+        (
+            "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6",
+            false,
+            "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+        ),
+        ("6002751e", false, "bc1sw50qgdz25j"),
+        ("5210751e76e8199196d454941c45d1b3a323", false, "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs"),
+        (
+            "0020000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+            true,
+            "tb1qqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesrxh6hy",
+        ),
+        (
+            "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+            true,
+            "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+        ),
+        (
+            "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            false,
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+        ),
+        // Pay-to-anchor
+        ("51024e73", false, "bc1pfeessrawgf"),
+    ];
+
+    for (script_hex, testnet, expected) in vectors {
+        assert_eq!(address(script_hex, testnet).unwrap(), expected, "script {}", script_hex);
+    }
+}
+
+#[test]
+fn test_scripts_without_address() {
+    // OP_RETURN
+    assert_eq!(address("6a0b68656c6c6f20776f726c64", false), None);
+    // P2PK
+    assert_eq!(
+        address("2102c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5ac", false),
+        None
+    );
+    // Version 0 program with an invalid length
+    assert_eq!(address("0010751e76e8199196d454941c45d1b3a323", false), None);
+    // P2PKH template followed by an extra opcode
+    assert_eq!(address("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac61", false), None);
+    assert_eq!(address("", false), None);
+}
@@ -0,0 +1,78 @@
+use crate::utils::compact::{
+    bigint_to_u256, block_work, decode_compact, difficulty, encode_compact, parse_bits, parse_u256, to_hex_u256,
+    u256_to_bigint,
+};
+use primitive_types::U256;
+
+const GENESIS_TARGET: &str = "00000000ffff0000000000000000000000000000000000000000000000000000";
+
+#[test]
+fn test_decode_genesis_bits() {
+    let bits = parse_bits("1d00ffff").unwrap();
+    assert_eq!(bits, 0x1d00ffff);
+    assert_eq!(to_hex_u256(decode_compact(bits).unwrap()), GENESIS_TARGET);
+}
+
+#[test]
+fn test_decode_compact_vectors() {
+    // Vectors from Bitcoin Core's arith_uint256 tests
+    assert_eq!(decode_compact(0x00000000).unwrap(), U256::zero());
+    assert_eq!(decode_compact(0x01003456).unwrap(), U256::zero());
+    assert_eq!(decode_compact(0x02000056).unwrap(), U256::zero());
+    assert_eq!(decode_compact(0x01123456).unwrap(), U256::from(0x12));
+    assert_eq!(decode_compact(0x02008000).unwrap(), U256::from(0x80));
+    assert_eq!(decode_compact(0x05009234).unwrap(), U256::from(0x92340000u64));
+    assert_eq!(decode_compact(0x20123456).unwrap(), U256::from(0x123456) << 232);
+}
+
+#[test]
+fn test_decode_compact_rejects_invalid_targets() {
+    assert!(decode_compact(0x04923456).is_err());
+    assert!(decode_compact(0x01fedcba).is_err());
+    assert!(decode_compact(0xff123456).is_err());
+    assert!(decode_compact(0x21010000).is_err());
+    assert!(parse_bits("not bits").is_err());
+}
+
+#[test]
+fn test_block_work() {
+    let target = parse_u256(GENESIS_TARGET).unwrap();
+    assert_eq!(block_work(target), U256::from(0x1_0001_0001u64));
+    assert_eq!(block_work(U256::zero()), U256::zero());
+    assert_eq!(block_work(U256::MAX), U256::one());
+}
+
+#[test]
+fn test_chainwork_hex_matches_rpc() {
+    // getblock reports the chainwork of block 1 as two genesis-difficulty blocks
+    let work = block_work(decode_compact(0x1d00ffff).unwrap());
+    assert_eq!(to_hex_u256(work + work), "0000000000000000000000000000000000000000000000000000000200020002");
+    assert_eq!(parse_u256("0000000000000000000000000000000000000000000000000000000200020002").unwrap(), work + work);
+}
+
+#[test]
+fn test_bigint_round_trip() {
+    let value = U256::from(0x1_0001_0001u64) << 200;
+    assert_eq!(bigint_to_u256(&u256_to_bigint(value)), Some(value));
+    assert_eq!(bigint_to_u256(&u256_to_bigint(U256::zero())), Some(U256::zero()));
+    assert_eq!(bigint_to_u256(&substreams::scalar::BigInt::from(-1)), None);
+}
+
+#[test]
+fn test_encode_compact() {
+    assert_eq!(encode_compact(U256::zero()), 0x00000000);
+    assert_eq!(encode_compact(U256::from(0x12)), 0x01120000);
+    assert_eq!(encode_compact(U256::from(0x80)), 0x02008000);
+    assert_eq!(encode_compact(U256::from(0x92340000u64)), 0x05009234);
+    assert_eq!(encode_compact(U256::from(0x123456) << 232), 0x20123456);
+    assert_eq!(encode_compact(parse_u256(GENESIS_TARGET).unwrap()), 0x1d00ffff);
+    // Precision below the 3-byte mantissa is dropped
+    assert_eq!(encode_compact(U256::from(0x12345678u64)), 0x04123456);
+}
+
+#[test]
+fn test_difficulty() {
+    assert_eq!(difficulty(0x1d00ffff), 1.0);
+    assert!((difficulty(0x1b0404cb) - 16_307.420938523983).abs() < 1e-6);
+    assert_eq!(difficulty(0x1d000000), 0.0);
+}
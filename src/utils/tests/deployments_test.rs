@@ -0,0 +1,32 @@
+use crate::utils::deployments::{ActivationMode, Deployments};
+
+#[test]
+fn test_embedded_deployments() {
+    let deployments = Deployments::load("").unwrap().deployments;
+    let names: Vec<&str> = deployments.iter().map(|deployment| deployment.name.as_str()).collect();
+    assert_eq!(names, vec!["csv", "segwit", "taproot"]);
+
+    let taproot = &deployments[2];
+    assert_eq!(taproot.bit, 2);
+    assert_eq!(taproot.period, 2016);
+    assert_eq!(taproot.threshold, 1815);
+    assert_eq!(taproot.min_activation_height, 709_632);
+    assert_eq!(taproot.mode, ActivationMode::Bip9);
+}
+
+#[test]
+fn test_params_replace_embedded_deployments() {
+    let params = r#"{"deployments": [{"name": "test", "bit": 28, "start": 100, "timeout": 200, "threshold": 108, "period": 144, "mode": "bip8", "lock_in_on_timeout": true}]}"#;
+    let deployments = Deployments::load(params).unwrap().deployments;
+    assert_eq!(deployments.len(), 1);
+    assert_eq!(deployments[0].period, 144);
+    assert_eq!(deployments[0].mode, ActivationMode::Bip8);
+    assert!(deployments[0].lock_in_on_timeout);
+}
+
+#[test]
+fn test_invalid_deployments() {
+    assert!(Deployments::load("not json").is_err());
+    assert!(Deployments::load(r#"{"deployments": [{"name": "x", "bit": 29, "start": 0, "timeout": 1, "threshold": 1}]}"#).is_err());
+    assert!(Deployments::load(r#"{"deployments": [{"name": "x", "bit": 1, "start": 0, "timeout": 1, "threshold": 1, "period": 0}]}"#).is_err());
+}
@@ -0,0 +1,51 @@
+use crate::utils::hashes::{merkle_root, sha256d};
+use crate::utils::ids::{TxMerkleRoot, Txid};
+
+fn txids(hexes: &[&str]) -> Vec<[u8; 32]> {
+    hexes.iter().map(|hex| Txid::from_hex(hex).unwrap().to_byte_array()).collect()
+}
+
+#[test]
+fn test_sha256d() {
+    assert_eq!(hex::encode(sha256d(b"")), "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456");
+}
+
+#[test]
+fn test_merkle_root_of_single_transaction() {
+    // Genesis block: the root is the coinbase txid
+    let leaves = txids(&["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"]);
+    let (root, mutated) = merkle_root(&leaves);
+    assert_eq!(TxMerkleRoot::from_byte_array(root).to_string(), "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b");
+    assert!(!mutated);
+}
+
+#[test]
+fn test_merkle_root_of_block_100000() {
+    let leaves = txids(&[
+        "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+        "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+        "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+        "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+    ]);
+    let (root, mutated) = merkle_root(&leaves);
+    assert_eq!(TxMerkleRoot::from_byte_array(root).to_string(), "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766");
+    assert!(!mutated);
+}
+
+#[test]
+fn test_merkle_root_mutation() {
+    let leaves = txids(&[
+        "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+        "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+        "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+    ]);
+    let (root, mutated) = merkle_root(&leaves);
+    assert!(!mutated);
+
+    // Repeating the last transaction gives the same root, and is flagged
+    let mut duplicated = leaves.clone();
+    duplicated.push(leaves[2]);
+    assert_eq!(merkle_root(&duplicated), (root, true));
+
+    assert_eq!(merkle_root(&[]), ([0u8; 32], false));
+}
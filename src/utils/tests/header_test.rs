@@ -0,0 +1,94 @@
+use crate::utils::header::{check_proof_of_work, header_hash, serialize_header};
+use crate::utils::ids::BlockHash;
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::Block;
+
+/// Header fields of a mainnet block, as reported by Firehose
+fn header(hash: &str, previous_hash: &str, merkle_root: &str, version: i32, time: i64, bits: &str, nonce: u32) -> Block {
+    Block {
+        hash: hash.to_string(),
+        previous_hash: previous_hash.to_string(),
+        merkle_root: merkle_root.to_string(),
+        version,
+        time,
+        bits: bits.to_string(),
+        nonce,
+        ..Default::default()
+    }
+}
+
+fn genesis() -> Block {
+    header(
+        "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+        "",
+        "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+        1,
+        1_231_006_505,
+        "1d00ffff",
+        2_083_236_893,
+    )
+}
+
+#[test]
+fn test_serialize_genesis_header() {
+    assert_eq!(
+        hex::encode(serialize_header(&genesis()).unwrap()),
+        "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c"
+    );
+}
+
+#[test]
+fn test_mainnet_header_hashes() {
+    let blocks = [
+        genesis(),
+        // Block 100000
+        header(
+            "000000000003ba27aa200b1cecaad478d2b00432346c3f1f3986da1afd33e506",
+            "000000000002d01c1fccc21636b607dfd930d31d01c3a62104612a1719011250",
+            "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766",
+            1,
+            1_293_623_863,
+            "1b04864c",
+            274_148_111,
+        ),
+        // Block 125552
+        header(
+            "00000000000000001e8d6829a8a21adc5d38d0a473b144b6765798e61f98bd1d",
+            "00000000000008a3a41b85b8b29ad444def299fee21793cd8b9e567eab02cd81",
+            "2b12fcf1b09288fcaff797d71e950e71ae42b91e8bdb2304758dfcffc2b620e3",
+            1,
+            1_305_998_791,
+            "1a44b9f2",
+            2_504_433_986,
+        ),
+    ];
+
+    for block in blocks {
+        let hash = header_hash(&block).unwrap();
+        assert_eq!(hash.to_string(), block.hash);
+        assert!(check_proof_of_work(hash, u32::from_str_radix(&block.bits, 16).unwrap()).unwrap());
+    }
+}
+
+#[test]
+fn test_check_proof_of_work() {
+    let hash = BlockHash::from_hex("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f").unwrap();
+    assert!(check_proof_of_work(hash, 0x1d00ffff).unwrap());
+    // Block 100000's target is lower than the genesis hash
+    assert!(!check_proof_of_work(hash, 0x1b04864c).unwrap());
+    // A hash equal to the target is valid
+    let target = BlockHash::from_hex("00000000ffff0000000000000000000000000000000000000000000000000000").unwrap();
+    assert!(check_proof_of_work(target, 0x1d00ffff).unwrap());
+    assert!(!check_proof_of_work(hash, 0).unwrap());
+    assert!(check_proof_of_work(hash, 0x04923456).is_err());
+}
+
+#[test]
+fn test_invalid_header_fields() {
+    let mut block = genesis();
+    block.merkle_root = "not a hash".to_string();
+    assert!(serialize_header(&block).is_err());
+
+    let mut block = genesis();
+    block.time = -1;
+    assert!(serialize_header(&block).is_err());
+}
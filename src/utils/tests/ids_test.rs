@@ -0,0 +1,56 @@
+use crate::utils::hashes::sha256d;
+use crate::utils::ids::{BlockHash, Txid, Wtxid};
+
+/// Genesis block hash and coinbase txid, in display byte order
+const GENESIS_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+const GENESIS_COINBASE_TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+#[test]
+fn test_display_byte_order() {
+    let hash = BlockHash::from_hex(GENESIS_HASH).unwrap();
+    // Internal order is reversed: the leading zeros of the proof of work come last
+    let bytes = hash.to_byte_array();
+    assert_eq!(bytes[0], 0x6f);
+    assert_eq!(bytes[24..], [0x68, 0xd6, 0x19, 0, 0, 0, 0, 0]);
+    assert_eq!(hash.to_string(), GENESIS_HASH);
+    assert_eq!(BlockHash::from_byte_array(bytes), hash);
+
+    let txid = Txid::from_hex(GENESIS_COINBASE_TXID).unwrap();
+    assert_eq!(txid.to_byte_array()[0], 0x3b);
+    assert_eq!(txid.to_string(), GENESIS_COINBASE_TXID);
+}
+
+/// BIP143 native P2WPKH sample transaction, split around its segwit marker and witness
+const SEGWIT_VERSION: &str = "01000000";
+const SEGWIT_INPUTS: &str = "02fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000494830450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed01eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff";
+const SEGWIT_OUTPUTS: &str = "02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac";
+const SEGWIT_WITNESS: &str = "000247304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee0121025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357";
+const SEGWIT_LOCK_TIME: &str = "11000000";
+
+#[test]
+fn test_txid_and_wtxid_of_segwit_transaction() {
+    // The txid hashes the serialization without witness data, the wtxid the full one
+    let stripped = hex::decode([SEGWIT_VERSION, SEGWIT_INPUTS, SEGWIT_OUTPUTS, SEGWIT_LOCK_TIME].concat()).unwrap();
+    let full = hex::decode(
+        [SEGWIT_VERSION, "0001", SEGWIT_INPUTS, SEGWIT_OUTPUTS, SEGWIT_WITNESS, SEGWIT_LOCK_TIME].concat(),
+    )
+    .unwrap();
+
+    let txid = Txid::from_byte_array(sha256d(&stripped));
+    let wtxid = Wtxid::from_byte_array(sha256d(&full));
+    assert_eq!(txid.to_string(), "e8151a2af31c368a35053ddd4bdb285a8595c769a3ad83e0fa02314a602d4609");
+    assert_eq!(wtxid.to_string(), "c36c38370907df2324d9ce9d149d191192f338b37665a82e78e76a12c909b762");
+}
+
+#[test]
+fn test_from_hex_rejects_invalid_ids() {
+    assert!(Txid::from_hex("").is_err());
+    assert!(Txid::from_hex("4a5e1e").is_err());
+    assert!(Txid::from_hex(&format!("{}00", GENESIS_COINBASE_TXID)).is_err());
+    assert!(Txid::from_hex(&GENESIS_COINBASE_TXID.replace('a', "z")).is_err());
+    // Upper case hex is accepted and normalized
+    assert_eq!(
+        Txid::from_hex(&GENESIS_COINBASE_TXID.to_uppercase()).unwrap().to_string(),
+        GENESIS_COINBASE_TXID
+    );
+}
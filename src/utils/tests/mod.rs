@@ -0,0 +1,9 @@
+mod amount_test;
+mod bitcoin_utils_test;
+mod compact_test;
+mod deployments_test;
+mod hashes_test;
+mod header_test;
+mod ids_test;
+mod pools_test;
+mod script_test;
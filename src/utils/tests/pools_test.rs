@@ -0,0 +1,67 @@
+use crate::utils::pools::{PoolDatabase, PoolMatchMethod};
+use substreams_bitcoin::pb::sf::bitcoin::r#type::v1::{ScriptPubKey, Transaction, Vin, Vout};
+
+fn coinbase_tx(coinbase: &[u8], payout_script_hex: &str) -> Transaction {
+    Transaction {
+        vin: vec![Vin {
+            coinbase: hex::encode(coinbase),
+            ..Default::default()
+        }],
+        vout: vec![Vout {
+            value: 3.2,
+            script_pub_key: Some(ScriptPubKey {
+                hex: payout_script_hex.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_embedded_database_loads() {
+    let pools = PoolDatabase::load("").unwrap();
+    assert!(!pools.version.is_empty());
+    assert!(!pools.coinbase_tags.is_empty());
+    assert!(!pools.payout_addresses.is_empty());
+}
+
+#[test]
+fn test_identify_by_coinbase_tag() {
+    let pools = PoolDatabase::load("").unwrap();
+    let tx = coinbase_tx(b"\x03\x40\x4d\x0c/Foundry USA Pool/#dropgold/", "6a24aa21a9ed");
+
+    let pool = pools.identify(&tx);
+    assert_eq!(pool.name, "Foundry USA");
+    assert_eq!(pool.method, PoolMatchMethod::CoinbaseTag);
+}
+
+#[test]
+fn test_identify_by_payout_address() {
+    let pools = PoolDatabase::load("").unwrap();
+    // 12dRugNcdxK39288NjcDV4GX7rMsKCGn6B, with a tag pointing to another pool
+    let tx = coinbase_tx(b"\x03\x40\x4d\x0c/ViaBTC/", "76a91411dbe48cc6b617f9c6adaf4d9ed5f625b1c7cb5988ac");
+
+    let pool = pools.identify(&tx);
+    assert_eq!(pool.name, "AntPool");
+    assert_eq!(pool.method, PoolMatchMethod::PayoutAddress);
+}
+
+#[test]
+fn test_params_override_embedded_entries() {
+    let params = r#"{
+        "version": "custom-1",
+        "coinbase_tags": { "/ViaBTC/": { "name": "Renamed Pool", "link": "" } }
+    }"#;
+    let pools = PoolDatabase::load(params).unwrap();
+    assert_eq!(pools.version, "custom-1");
+
+    let pool = pools.identify(&coinbase_tx(b"/ViaBTC/Mined by user", ""));
+    assert_eq!(pool.name, "Renamed Pool");
+
+    let pool = pools.identify(&coinbase_tx(b"nothing to see here", ""));
+    assert_eq!(pool.method, PoolMatchMethod::Unknown);
+
+    assert!(PoolDatabase::load("not json").is_err());
+}
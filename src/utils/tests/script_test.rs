@@ -0,0 +1,129 @@
+use crate::utils::script::{
+    classify, instructions, is_unspendable, read_script_num, small_int_value, Instruction, ScriptType, MAX_SCRIPT_SIZE,
+};
+
+#[test]
+fn test_instructions() {
+    // OP_0, a direct push, OP_PUSHDATA1, OP_PUSHDATA2, OP_CHECKSIG
+    let script = hex::decode("0002abcd4c01ef4d0200beefac").unwrap();
+    let parsed: Vec<Instruction> = instructions(&script).map(Result::unwrap).collect();
+    assert_eq!(
+        parsed,
+        vec![
+            Instruction::PushBytes(&[]),
+            Instruction::PushBytes(&[0xab, 0xcd]),
+            Instruction::PushBytes(&[0xef]),
+            Instruction::PushBytes(&[0xbe, 0xef]),
+            Instruction::Op(0xac),
+        ]
+    );
+}
+
+#[test]
+fn test_truncated_push_ends_iteration() {
+    let script = hex::decode("51034142").unwrap();
+    let mut parsed = instructions(&script);
+    assert_eq!(parsed.next().unwrap().unwrap(), Instruction::Op(0x51));
+    assert!(parsed.next().unwrap().is_err());
+    assert!(parsed.next().is_none());
+
+    assert!(instructions(&[0x4d, 0x01]).next().unwrap().is_err());
+}
+
+#[test]
+fn test_read_script_num() {
+    assert_eq!(read_script_num(&[], true), Some(0));
+    assert_eq!(read_script_num(&[0x01], true), Some(1));
+    assert_eq!(read_script_num(&[0x81], true), Some(-1));
+    assert_eq!(read_script_num(&[0xff, 0x00], true), Some(255));
+    assert_eq!(read_script_num(&[0xff, 0x80], true), Some(-255));
+    assert_eq!(read_script_num(&[0x5b, 0x7a, 0x03], true), Some(227_931));
+
+    // Non-minimal encodings
+    assert_eq!(read_script_num(&[0x00], true), None);
+    assert_eq!(read_script_num(&[0x80], true), None);
+    assert_eq!(read_script_num(&[0x01, 0x00], true), None);
+    assert_eq!(read_script_num(&[0x01, 0x00], false), Some(1));
+    assert_eq!(read_script_num(&[0; 9], false), None);
+}
+
+#[test]
+fn test_small_int_value() {
+    assert_eq!(small_int_value(0x00), Some(0));
+    assert_eq!(small_int_value(0x51), Some(1));
+    assert_eq!(small_int_value(0x60), Some(16));
+    assert_eq!(small_int_value(0x4f), None);
+    assert_eq!(small_int_value(0xac), None);
+}
+
+fn classify_hex(script: &str) -> ScriptType {
+    classify(&hex::decode(script).unwrap())
+}
+
+#[test]
+fn test_classify_standard_templates() {
+    let hash20 = "89abcdefabbaabbaabbaabbaabbaabbaabbaabba";
+    let hash32 = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+    let compressed = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    let uncompressed = "0411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3";
+
+    assert_eq!(classify_hex(&format!("21{}ac", compressed)), ScriptType::P2pk { compressed: true });
+    assert_eq!(classify_hex(&format!("41{}ac", uncompressed)), ScriptType::P2pk { compressed: false });
+    assert_eq!(classify_hex(&format!("76a914{}88ac", hash20)), ScriptType::P2pkh);
+    assert_eq!(classify_hex(&format!("a914{}87", hash20)), ScriptType::P2sh);
+    assert_eq!(classify_hex(&format!("0014{}", hash20)), ScriptType::P2wpkh);
+    assert_eq!(classify_hex(&format!("0020{}", hash32)), ScriptType::P2wsh);
+    assert_eq!(classify_hex(&format!("5120{}", hash32)), ScriptType::P2tr);
+    assert_eq!(classify_hex("51024e73"), ScriptType::P2a);
+    assert_eq!(classify_hex(&format!("5220{}", hash32)), ScriptType::WitnessUnknown { version: 2 });
+    assert_eq!(classify_hex("6a0b68656c6c6f20776f726c64"), ScriptType::OpReturn);
+    assert_eq!(classify_hex("6a"), ScriptType::OpReturn);
+
+    let multisig = format!("5221{}41{}21{}53ae", compressed, uncompressed, compressed);
+    assert_eq!(classify_hex(&multisig), ScriptType::Multisig { required: 2, total: 3 });
+}
+
+#[test]
+fn test_classify_rejects_loose_matches() {
+    let hash20 = "89abcdefabbaabbaabbaabbaabbaabbaabbaabba";
+    let compressed = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    // Prefix and suffix match but the hash has the wrong length
+    assert_eq!(classify_hex(&format!("a913{}87", &hash20[2..])), ScriptType::NonStandard);
+    assert_eq!(classify_hex(&format!("a914{}88", hash20)), ScriptType::NonStandard);
+    assert_eq!(classify_hex(&format!("76a915{}0088ac", hash20)), ScriptType::NonStandard);
+    // A push that is not a public key, and a P2PK with trailing data
+    assert_eq!(classify_hex(&format!("21{}ac", "05".repeat(33))), ScriptType::NonStandard);
+    assert_eq!(classify_hex(&format!("21{}acac", compressed)), ScriptType::NonStandard);
+    // Version 0 programs other than 20 or 32 bytes
+    assert_eq!(classify_hex("0010000102030405060708090a0b0c0d0e0f"), ScriptType::NonStandard);
+    // Ends with OP_CHECKMULTISIG but the key count does not match n
+    assert_eq!(classify_hex(&format!("5121{}52ae", compressed)), ScriptType::NonStandard);
+    assert_eq!(classify_hex(&format!("5221{}51ae", compressed)), ScriptType::NonStandard);
+    assert_eq!(classify_hex("ae"), ScriptType::NonStandard);
+    // OP_RETURN followed by a non-push opcode or a truncated push
+    assert_eq!(classify_hex("6a76"), ScriptType::NonStandard);
+    assert_eq!(classify_hex("6a0501"), ScriptType::NonStandard);
+    assert_eq!(classify(&[]), ScriptType::NonStandard);
+}
+
+#[test]
+fn test_script_type_names() {
+    assert_eq!(ScriptType::P2pk { compressed: false }.name(), "P2PK");
+    assert_eq!(ScriptType::P2a.name(), "P2A");
+    assert_eq!(ScriptType::WitnessUnknown { version: 16 }.name(), "WITNESS_V16");
+    assert_eq!(ScriptType::Multisig { required: 1, total: 2 }.name(), "MULTISIG");
+    assert_eq!(ScriptType::NonStandard.name(), "NONSTANDARD");
+}
+
+#[test]
+fn test_is_unspendable() {
+    // Standard null data, and OP_RETURN followed by anything, classified NONSTANDARD
+    assert!(is_unspendable(&hex::decode("6a0b68656c6c6f20776f726c64").unwrap()));
+    assert!(is_unspendable(&[0x6a, 0x4c]));
+    assert_eq!(classify(&[0x6a, 0x4c]), ScriptType::NonStandard);
+    assert!(is_unspendable(&vec![0x51; MAX_SCRIPT_SIZE + 1]));
+    assert!(!is_unspendable(&vec![0x51; MAX_SCRIPT_SIZE]));
+    assert!(!is_unspendable(&hex::decode("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()));
+    assert!(!is_unspendable(&[]));
+}